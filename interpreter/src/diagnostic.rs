@@ -0,0 +1,134 @@
+use crate::interpreter::InterpreterError;
+use crate::resolver::{ResolverError, ResolverWarning};
+use serde::Serialize;
+use std::fmt::Display;
+use std::io::IsTerminal;
+use syntax::ScannerError;
+use syntax::fold::FoldError;
+use syntax::parser::ParserError;
+
+/// An error or warning found while running a script, in a shape every stage of the interpreter
+/// shares. It is serialized for `--message-format=json`.
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    pub line: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+    pub stage: Stage,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    /// Wraps the text in the ANSI escape sequences of the color of the severity: red for
+    /// errors and yellow for warnings.
+    pub fn paint(self, text: impl Display) -> String {
+        let color = match self {
+            Severity::Error => "\x1b[31m",
+            Severity::Warning => "\x1b[33m",
+        };
+        format!("{color}{text}\x1b[0m")
+    }
+}
+
+/// Whether diagnostics written for humans are colored, chosen with `--color` or `--no-color`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    /// Colors are used when the diagnostic is written to a terminal.
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Parses the value of `--color=<choice>`.
+    pub fn parse(choice: &str) -> Option<Self> {
+        match choice {
+            "auto" => Some(Self::Auto),
+            "always" => Some(Self::Always),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+
+    /// Returns the text colored by its severity if colors are enabled for `stream`.
+    pub fn paint(self, severity: Severity, text: impl Display, stream: impl IsTerminal) -> String {
+        let enabled = match self {
+            Self::Auto => stream.is_terminal(),
+            Self::Always => true,
+            Self::Never => false,
+        };
+
+        if enabled {
+            severity.paint(text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// The part of the interpreter that found the error.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    Scan,
+    Parse,
+    Fold,
+    Resolve,
+    Runtime,
+}
+
+impl Diagnostic {
+    fn error(stage: Stage, line: Option<usize>, message: String) -> Self {
+        Self {
+            line,
+            severity: Severity::Error,
+            message,
+            stage,
+        }
+    }
+}
+
+impl From<&ScannerError> for Diagnostic {
+    fn from(error: &ScannerError) -> Self {
+        Self::error(Stage::Scan, Some(error.line), error.error_type.to_string())
+    }
+}
+
+impl From<&ParserError> for Diagnostic {
+    fn from(error: &ParserError) -> Self {
+        Self::error(Stage::Parse, error.line(), error.to_string())
+    }
+}
+
+impl From<&FoldError> for Diagnostic {
+    fn from(error: &FoldError) -> Self {
+        Self::error(Stage::Fold, Some(error.line()), error.to_string())
+    }
+}
+
+impl From<&ResolverError> for Diagnostic {
+    fn from(error: &ResolverError) -> Self {
+        Self::error(Stage::Resolve, error.line(), error.to_string())
+    }
+}
+
+impl From<&ResolverWarning> for Diagnostic {
+    fn from(warning: &ResolverWarning) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(Stage::Resolve, Some(warning.line()), warning.to_string())
+        }
+    }
+}
+
+impl From<&InterpreterError> for Diagnostic {
+    fn from(error: &InterpreterError) -> Self {
+        Self::error(Stage::Runtime, Some(error.token.line()), error.message())
+    }
+}
@@ -4,25 +4,48 @@ mod error;
 mod native;
 mod value;
 
-use crate::interpreter::callable::{Callable, NativeFunc};
+use crate::interpreter::callable::{Arity, Callable, NativeFunc};
 use crate::interpreter::environment::Environment;
+use crate::resolver::Resolver;
 use callable::LoxFunction;
 pub use error::*;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::io::{BufRead, Cursor, Write};
+use std::process::ExitCode;
 use std::rc::Rc;
 use syntax::Expression;
+use syntax::fold::MAX_SAFE_INTEGER;
 pub use syntax::statement::Statement;
 use syntax::token::{Token, TokenType};
-use value::Field;
 pub use value::LoxValue;
+use value::{Field, Instance};
 
 type RcEnvironment = Rc<RefCell<Environment>>;
 
+/// The prelude loaded before every script and REPL session.
+pub const PRELUDE: &str = include_str!("interpreter/prelude.lox");
+
+/// The longest string, in bytes, that repeating a string with `*` may build.
+const MAX_REPEATED_LENGTH: usize = 1 << 30;
+
 pub struct Interpreter {
     globals: RcEnvironment,
     environment_stack: RefCell<Vec<RcEnvironment>>,
+    /// The instance each running function is bound to, so `this` does not walk the environment
+    /// chain. Functions that are not bound methods push `None`.
+    this_stack: RefCell<Vec<Option<Rc<Instance>>>>,
     locals: RefCell<HashMap<Expression, usize>>,
+    global_declarations: RefCell<HashMap<String, Option<usize>>>,
+    output: RefCell<Box<dyn Write>>,
+    /// Where `eprint`, `eprintln` and interpreter warnings are written.
+    error_output: RefCell<Box<dyn Write>>,
+    /// Where `read_line` and `input` read from. `None` stands for the standard input, which is
+    /// not locked for good because the REPL reads from it too.
+    input: RefCell<Option<Box<dyn BufRead>>>,
+    trace: bool,
+    ieee_division: bool,
+    warn_precision: bool,
 }
 
 #[must_use]
@@ -31,6 +54,8 @@ enum ControlFlow {
     BreakLoop,
     ContinueLoop,
     Return(LoxValue),
+    /// A thrown value, along with the `throw` keyword that raised it.
+    Throw(LoxValue, Token),
 }
 
 macro_rules! interpreter_error {
@@ -42,65 +67,303 @@ macro_rules! interpreter_error {
     }};
 }
 
+/// Orders the statements of a scope so that function declarations run first, which lets code
+/// call functions declared further down. Every other statement keeps its order. Classes are not
+/// hoisted, because declaring one evaluates its superclass and static fields.
+fn hoisted(statements: &[Statement]) -> impl Iterator<Item = &Statement> {
+    let is_function = |s: &&Statement| matches!(s, Statement::FunctionDeclaration(_));
+    let others = statements.iter().filter(move |s| !is_function(s));
+    statements.iter().filter(is_function).chain(others)
+}
+
 impl Interpreter {
     pub fn new() -> Self {
+        Self::with_output(std::io::stdout())
+    }
+
+    /// Creates an interpreter whose `print` statements (and trace logs) are written to `output`
+    /// instead of the standard output.
+    pub fn with_output(output: impl Write + 'static) -> Self {
         let ref_cell = Rc::new(RefCell::new(Environment::new()));
         let globals = ref_cell;
         let interpreter = Self {
             environment_stack: RefCell::new(vec![globals.clone()]),
+            this_stack: RefCell::new(Vec::new()),
             globals,
             locals: RefCell::new(HashMap::new()),
+            global_declarations: RefCell::new(HashMap::new()),
+            output: RefCell::new(Box::new(output)),
+            error_output: RefCell::new(Box::new(std::io::stderr())),
+            input: RefCell::new(None),
+            trace: false,
+            ieee_division: false,
+            warn_precision: false,
         };
         interpreter.load_native_functions();
 
         interpreter
     }
 
-    pub fn interpret(&self, statements: &[Statement]) -> InterpreterResult<()> {
-        for statement in statements {
-            let _ = self.execute_statement(statement, false)?;
+    /// Makes `read_line` and `input` read from `input` instead of the standard input. Only the
+    /// tests feed the interpreter a fake input for now.
+    #[cfg(test)]
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.input = RefCell::new(Some(Box::new(input)));
+    }
+
+    /// Makes `eprint`, `eprintln` and warnings write to `error_output` instead of the standard
+    /// error. Only the tests capture it for now.
+    #[cfg(test)]
+    pub fn set_error_output(&mut self, error_output: impl Write + 'static) {
+        self.error_output = RefCell::new(Box::new(error_output));
+    }
+
+    /// When enabled, every statement is logged with its source line before being executed, along
+    /// with the value produced by expression statements.
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+
+    /// When enabled, dividing by zero follows IEEE-754 and produces `inf`, `-inf` or `nan`
+    /// instead of raising a division by zero error.
+    pub fn set_ieee_division(&mut self, ieee_division: bool) {
+        self.ieee_division = ieee_division;
+    }
+
+    pub fn ieee_division(&self) -> bool {
+        self.ieee_division
+    }
+
+    /// When enabled, a warning is printed to the standard error whenever arithmetic produces a
+    /// number beyond 2^53, past which integers can no longer be represented exactly.
+    pub fn set_warn_precision(&mut self, warn_precision: bool) {
+        self.warn_precision = warn_precision;
+    }
+
+    /// Executes the statements in order. Returns the exit code requested by the script if it
+    /// called `exit`, in which case the remaining statements are not run.
+    pub fn interpret(&self, statements: &[Statement]) -> InterpreterResult<Option<ExitCode>> {
+        for statement in hoisted(statements) {
+            match self.execute_statement(statement, false) {
+                Ok(ControlFlow::Throw(value, keyword)) => {
+                    return interpreter_error!(InterpreterErrorType::Thrown(value), keyword);
+                }
+                Ok(_) => {}
+                Err(e) => match e.error_type {
+                    InterpreterErrorType::Exit(code) => {
+                        return Ok(Some(ExitCode::from(code)));
+                    }
+                    _ => return Err(e),
+                },
+            }
+        }
+        Ok(None)
+    }
+
+    /// Runs a prelude written in Lox, so that the globals it defines are available to the code
+    /// interpreted afterwards.
+    pub fn load_prelude(&self, source: &str) -> Result<(), PreludeError> {
+        let tokens = syntax::Scanner::new(Cursor::new(source)).scan_tokens()?;
+        let mut statements = syntax::Parser::new(&tokens)
+            .statements()
+            .map_err(Box::new)?;
+        if let Some(error) = syntax::fold::fold_constants(&mut statements)
+            .into_iter()
+            .next()
+            && !self.ieee_division
+        {
+            return Err(error.into());
+        }
+        let declared: Vec<String> = self.global_declarations.borrow().keys().cloned().collect();
+        Resolver::new(self).resolve_statements(&statements)?;
+        /* The prelude has no lines the user can see, so shadow warnings must not point into it */
+        for (name, line) in self.global_declarations.borrow_mut().iter_mut() {
+            if !declared.contains(name) {
+                *line = None;
+            }
         }
+        self.interpret(&statements)?;
         Ok(())
     }
 
+    /// Evaluates the source as a single expression, such as `1 + 2`, and returns its value. It
+    /// is an error for the source to hold statements, or anything after the expression.
+    /// This is meant for embedding the interpreter, so the command line does not use it.
+    #[allow(dead_code)]
+    pub fn eval(&self, source: &str) -> InterpreterResult<LoxValue> {
+        /* Errors found before there is a token to point at use a placeholder one */
+        let invalid = |reason: String, token: Option<&Token>, line: usize| {
+            let token = token
+                .cloned()
+                .unwrap_or_else(|| Token::new(TokenType::Nil, String::new(), line));
+            interpreter_error!(InterpreterErrorType::InvalidExpression(reason), token)
+        };
+
+        let tokens = match syntax::Scanner::new(Cursor::new(source)).scan_tokens() {
+            Ok(tokens) => tokens,
+            Err(e) => return invalid(e.to_string(), None, e.line),
+        };
+        let last_token = tokens.last();
+
+        let mut parser = syntax::Parser::new(&tokens);
+        let expression = match parser.expression() {
+            Ok(expression) => expression,
+            Err(e) => return invalid(e.to_string(), last_token, 1),
+        };
+        if let Some(token) = parser.peek() {
+            let reason = format!("unexpected {} after the expression", token.display_lexeme());
+            return invalid(reason, Some(token), token.line());
+        }
+
+        if let Err(e) = Resolver::new(self).resolve_expression(&expression) {
+            return invalid(e.to_string(), last_token, 1);
+        }
+        self.evaluate(&expression)
+    }
+
     pub fn resolve(&self, expression: &Expression, depth: usize) {
         let mut locals = self.locals.borrow_mut();
         locals.insert(expression.clone(), depth);
     }
 
+    /// Removes every global variable, function and class, leaving only the native functions.
+    pub fn reset_globals(&self) {
+        *self.globals.borrow_mut() = Environment::new();
+        self.environment_stack.borrow_mut().truncate(1);
+        self.this_stack.borrow_mut().clear();
+        self.global_declarations.borrow_mut().clear();
+        self.locals.borrow_mut().clear();
+        self.load_native_functions();
+    }
+
+    /// Returns the sorted names of the globals defined by the program, leaving out the natives.
+    pub fn global_names(&self) -> Vec<String> {
+        let globals = self.globals.borrow();
+        let mut names: Vec<String> = globals
+            .variables()
+            .filter(|(_, value)| !matches!(value, LoxValue::Callable(c) if c.is_native()))
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Runs `f`, and then rolls back every global it defined or assigned. This lets the REPL
+    /// try code out without keeping its effects on the session.
+    pub fn speculatively<T>(&self, f: impl FnOnce() -> T) -> T {
+        let snapshot = self.globals.borrow().snapshot();
+        let declarations = self.global_declarations.borrow().clone();
+
+        let result = f();
+
+        self.globals.borrow_mut().restore(snapshot);
+        *self.global_declarations.borrow_mut() = declarations;
+        result
+    }
+
+    /// Removes a single global. Returns whether it was defined.
+    pub fn undefine_global(&self, name: &str) -> bool {
+        self.global_declarations.borrow_mut().remove(name);
+        self.globals.borrow_mut().undefine(name)
+    }
+
+    /// Forgets the declarations of globals that were never defined, like those after a runtime
+    /// error in a REPL input, so later inputs do not treat them as declared.
+    pub fn forget_undefined_globals(&self) {
+        let globals = self.globals.borrow();
+        self.global_declarations
+            .borrow_mut()
+            .retain(|name, _| globals.get(name).is_some());
+    }
+
+    /// Records that the program declares a global called `name`, so reading it before the
+    /// declaration runs can be told apart from reading a name that does not exist.
+    pub fn declare_global(&self, name: &str, line: Option<usize>) {
+        let mut declarations = self.global_declarations.borrow_mut();
+        declarations.entry(String::from(name)).or_insert(line);
+    }
+
+    /// Returns whether a global called `name` is currently defined, natives included.
+    pub fn is_global(&self, name: &str) -> bool {
+        self.globals.borrow().get(name).is_some()
+    }
+
+    /// Returns whether the program declares a global called `name`, along with the line of the
+    /// declaration if it is known.
+    pub fn global_declaration(&self, name: &str) -> Option<Option<usize>> {
+        self.global_declarations.borrow().get(name).copied()
+    }
+
+    fn undefined_variable<T>(&self, name: &str, token: &Token) -> InterpreterResult<T> {
+        /* Inside a method, a bare name is likely a sibling method called without `this.` */
+        let this = self.current_environment().borrow().get("this");
+        if let Some(LoxValue::Instance(instance)) = this
+            && instance.find_method(name).is_some()
+        {
+            return interpreter_error!(
+                InterpreterErrorType::MethodWithoutThis(String::from(name)),
+                token.clone()
+            );
+        }
+
+        let error_type = match self.global_declarations.borrow().get(name) {
+            Some(line) => InterpreterErrorType::AccessBeforeInitialization {
+                name: String::from(name),
+                declaration_line: *line,
+            },
+            None => InterpreterErrorType::UndefinedVariable(String::from(name)),
+        };
+
+        interpreter_error!(error_type, token.clone())
+    }
+
     fn execute_statement(
         &self,
         statement: &Statement,
         inside_loop: bool,
     ) -> InterpreterResult<ControlFlow> {
+        if self.trace {
+            let line = statement.line();
+            self.write_output(format_args!("[line {line}] {statement}\n"));
+        }
+
         match statement {
             Statement::Expression(expr) => {
-                self.evaluate(expr)?;
+                let result = self.evaluate(expr)?;
+                if self.trace {
+                    self.write_output(format_args!("=> {result}\n"));
+                }
                 Ok(ControlFlow::Normal)
             }
-            Statement::Print(expr) => {
-                let result = self.evaluate(expr)?;
-                println!("{result}");
+            Statement::Print(expressions) => {
+                let mut values = Vec::with_capacity(expressions.len());
+                for expr in expressions {
+                    /* Number literals print the way they were written, so `5.0` keeps its .0 */
+                    let value = match (expr, self.evaluate(expr)?) {
+                        (Expression::Number(_, form, _), LoxValue::Number(n)) => form.format(n),
+                        (_, value) => value.to_string(),
+                    };
+                    values.push(value);
+                }
+                self.write_output(format_args!("{}\n", values.join(" ")));
+                self.flush_output();
                 Ok(ControlFlow::Normal)
             }
-            Statement::VariableDeclaration { name, initializer } => {
+            Statement::VariableDeclaration {
+                name, initializer, ..
+            } => {
                 let initial = match initializer.as_ref() {
                     Some(initializer) => self.evaluate(initializer)?,
                     None => LoxValue::Nil,
                 };
-                let env_stack = self.environment_stack.borrow_mut();
-                let mut env = env_stack.last().unwrap().borrow_mut();
-                env.define(name.to_string(), initial);
+                self.current_environment()
+                    .borrow_mut()
+                    .define(name.to_string(), initial);
 
                 Ok(ControlFlow::Normal)
             }
-            Statement::Block(statements) => {
-                let current_env = {
-                    let env_stack = self.environment_stack.borrow_mut();
-                    env_stack.last().unwrap().clone()
-                };
-
-                let enclosure = Environment::new_enclosed(current_env);
+            Statement::Block { statements, .. } => {
+                let enclosure = Environment::new_enclosed(self.current_environment());
 
                 self.execute_block(statements, Rc::new(RefCell::new(enclosure)), inside_loop)
             }
@@ -108,6 +371,7 @@ impl Interpreter {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
                 let result = self.evaluate(condition)?.is_truthy();
 
@@ -119,72 +383,68 @@ impl Interpreter {
                     Ok(ControlFlow::Normal)
                 }
             }
-            Statement::While { condition, body } => {
+            Statement::While {
+                condition,
+                body,
+                else_branch,
+                ..
+            } => {
                 while self.evaluate(condition)?.is_truthy() {
                     match self.execute_statement(body, true)? {
-                        ControlFlow::BreakLoop => break,
-                        ControlFlow::Return(val) => return Ok(ControlFlow::Return(val)),
+                        ControlFlow::BreakLoop => return Ok(ControlFlow::Normal),
+                        flow @ (ControlFlow::Return(_) | ControlFlow::Throw(..)) => {
+                            return Ok(flow);
+                        }
                         ControlFlow::ContinueLoop => continue,
                         ControlFlow::Normal => {}
                     };
                 }
-                Ok(ControlFlow::Normal)
+                self.execute_loop_else(else_branch, inside_loop)
             }
             Statement::For {
                 initializer,
                 condition,
                 increment,
                 body,
+                else_branch,
+                ..
             } => {
-                if let Some(initializer) = initializer {
-                    let _ = self.execute_statement(initializer, false)?;
-                }
-
-                loop {
-                    if let Some(condition) = condition {
-                        if !self.evaluate(condition)?.is_truthy() {
-                            break;
-                        }
-                    }
-
-                    match self.execute_statement(body, true)? {
-                        ControlFlow::Normal => {}
-                        ControlFlow::BreakLoop => break,
-                        ControlFlow::Return(val) => return Ok(ControlFlow::Return(val)),
-                        ControlFlow::ContinueLoop => {
-                            if let Some(increment) = increment {
-                                self.evaluate(increment)?;
-                            }
-                            continue;
-                        }
-                    };
-
-                    if let Some(increment) = increment {
-                        self.evaluate(increment)?;
-                    }
+                /* The loop gets its own scope, so that the initializer does not leak */
+                let flow = self
+                    .in_new_scope(|| self.execute_for(initializer, condition, increment, body))?;
+                match flow {
+                    ControlFlow::BreakLoop => Ok(ControlFlow::Normal),
+                    ControlFlow::Normal => self.execute_loop_else(else_branch, inside_loop),
+                    flow => Ok(flow),
                 }
-
-                Ok(ControlFlow::Normal)
             }
             Statement::ClassDeclaration {
                 name,
                 methods,
+                static_fields,
+                static_methods,
                 super_class,
+                ..
             } => {
                 let super_class = match super_class {
                     Some(super_class) => Some(self.validate_superclass(super_class)?),
                     None => None,
                 };
 
-                let environment = {
-                    let env_stack = self.environment_stack.borrow_mut();
-                    env_stack.last().unwrap().clone()
-                };
+                let environment = self.current_environment();
+                environment
+                    .borrow_mut()
+                    .define(name.to_string(), LoxValue::Nil);
 
-                {
-                    let mut environment = environment.borrow_mut();
-                    environment.define(name.to_string(), LoxValue::Nil);
-                }
+                /* Methods of a subclass close over an environment where `super` is defined */
+                let method_closure = match &super_class {
+                    Some(super_class) => {
+                        let mut super_env = Environment::new_enclosed(environment.clone());
+                        super_env.define(String::from("super"), super_class.clone());
+                        Rc::new(RefCell::new(super_env))
+                    }
+                    None => environment.clone(),
+                };
 
                 let methods: HashMap<String, Rc<Callable>> = methods
                     .iter()
@@ -192,7 +452,7 @@ impl Interpreter {
                         (
                             m.name.to_string(),
                             Rc::new(Callable::LoxFunction(LoxFunction {
-                                closure: environment.clone(),
+                                closure: method_closure.clone(),
                                 is_initializer: m.name == "init",
                                 name: m.name.to_string(),
                                 params: m.parameters.clone(),
@@ -202,8 +462,30 @@ impl Interpreter {
                     })
                     .collect();
 
+                let super_class = super_class.as_ref().and_then(LoxValue::as_class).cloned();
                 let class = value::Class::new(name.to_string(), methods, super_class);
-                let arity = class.find_method("init").map(|m| m.arity()).unwrap_or(0);
+                for field in static_fields {
+                    let value = match &field.initializer {
+                        Some(initializer) => self.evaluate(initializer)?,
+                        None => LoxValue::Nil,
+                    };
+                    class.define_static(field.name.clone(), value);
+                }
+                for method in static_methods {
+                    let function = Callable::LoxFunction(LoxFunction {
+                        closure: environment.clone(),
+                        is_initializer: false,
+                        name: method.name.to_string(),
+                        params: method.parameters.clone(),
+                        block: method.body.clone(),
+                    });
+                    class.define_static(method.name.clone(), LoxValue::Callable(Rc::new(function)));
+                }
+
+                let arity = class
+                    .find_method("init")
+                    .map(|m| m.arity())
+                    .unwrap_or(Arity::Fixed(0));
 
                 let constructor = Callable::Constructor {
                     class: Rc::new(class),
@@ -219,8 +501,7 @@ impl Interpreter {
                 Ok(ControlFlow::Normal)
             }
             Statement::FunctionDeclaration(function) => {
-                let env_stack = self.environment_stack.borrow();
-                let current_env = env_stack.last().unwrap();
+                let current_env = self.current_environment();
 
                 let callable = Callable::LoxFunction(LoxFunction {
                     closure: current_env.clone(),
@@ -230,8 +511,9 @@ impl Interpreter {
                     block: function.body.clone(),
                 });
 
-                let mut global = self.globals.borrow_mut();
-                global.define(function.name.clone(), LoxValue::Callable(Rc::new(callable)));
+                current_env
+                    .borrow_mut()
+                    .define(function.name.clone(), LoxValue::Callable(Rc::new(callable)));
                 Ok(ControlFlow::Normal)
             }
             Statement::Return {
@@ -244,6 +526,62 @@ impl Interpreter {
                 };
                 Ok(ControlFlow::Return(value))
             }
+            Statement::Throw {
+                keyword,
+                expression,
+            } => {
+                let value = self.evaluate(expression)?;
+                Ok(ControlFlow::Throw(value, keyword.clone()))
+            }
+            Statement::Try {
+                body,
+                catch_name,
+                catch_body,
+                finally_body,
+                ..
+            } => {
+                let result = self.execute_try(body, catch_name, catch_body, inside_loop);
+                let Some(finally_body) = finally_body else {
+                    return result;
+                };
+
+                /* The pending result survives, unless the finally block leaves in its own way */
+                let finally_env = Environment::new_enclosed(self.current_environment());
+                match self.execute_block(
+                    finally_body,
+                    Rc::new(RefCell::new(finally_env)),
+                    inside_loop,
+                )? {
+                    ControlFlow::Normal => result,
+                    flow => Ok(flow),
+                }
+            }
+            Statement::With {
+                keyword,
+                resource,
+                body,
+            } => {
+                let resource = self.evaluate(resource)?;
+                let body_env = Environment::new_enclosed(self.current_environment());
+                let result = self.execute_block(body, Rc::new(RefCell::new(body_env)), inside_loop);
+
+                /* Like a finally block, an error raised by close replaces the body's result */
+                self.close_resource(&resource, keyword)?;
+                result
+            }
+            Statement::Match { value, arms, .. } => {
+                let value = self.evaluate(value)?;
+                for arm in arms {
+                    let matches = match &arm.pattern {
+                        Some(pattern) => self.evaluate(pattern)? == value,
+                        None => true,
+                    };
+                    if matches {
+                        return self.execute_statement(&arm.body, inside_loop);
+                    }
+                }
+                Ok(ControlFlow::Normal)
+            }
             Statement::Break { .. } if inside_loop => Ok(ControlFlow::BreakLoop),
             Statement::Continue { .. } if inside_loop => Ok(ControlFlow::ContinueLoop),
             Statement::Break { keyword } | Statement::Continue { keyword } => {
@@ -252,16 +590,175 @@ impl Interpreter {
         }
     }
 
-    fn validate_superclass(&self, expr: &Expression) -> InterpreterResult<Rc<value::Class>> {
-        match self.evaluate(expr)? {
-            LoxValue::Callable(callable) => match &*callable {
-                Callable::Native { .. } | Callable::LoxFunction(_) => {
-                    panic!("Super class must be a class")
+    /// Runs a `for` loop, returning [`ControlFlow::BreakLoop`] if it ended with a `break` so the
+    /// caller knows to skip the `else` branch.
+    fn execute_for(
+        &self,
+        initializer: &Option<Box<Statement>>,
+        condition: &Option<Expression>,
+        increment: &Option<Expression>,
+        body: &Statement,
+    ) -> InterpreterResult<ControlFlow> {
+        if let Some(initializer) = initializer {
+            let _ = self.execute_statement(initializer, false)?;
+        }
+
+        loop {
+            if let Some(condition) = condition
+                && !self.evaluate(condition)?.is_truthy()
+            {
+                break;
+            }
+
+            match self.execute_statement(body, true)? {
+                ControlFlow::Normal | ControlFlow::ContinueLoop => {}
+                flow @ (ControlFlow::BreakLoop
+                | ControlFlow::Return(_)
+                | ControlFlow::Throw(..)) => {
+                    return Ok(flow);
                 }
-                Callable::Constructor { class, .. } => Ok(class.clone()),
+            };
+
+            self.renew_loop_scope();
+            if let Some(increment) = increment {
+                self.evaluate(increment)?;
+            }
+        }
+
+        Ok(ControlFlow::Normal)
+    }
+
+    /// Runs the `else` branch of a loop that ended without a `break`.
+    fn execute_loop_else(
+        &self,
+        else_branch: &Option<Box<Statement>>,
+        inside_loop: bool,
+    ) -> InterpreterResult<ControlFlow> {
+        match else_branch {
+            Some(else_branch) => self.execute_statement(else_branch, inside_loop),
+            None => Ok(ControlFlow::Normal),
+        }
+    }
+
+    /// Replaces the scope of a `for` loop with a copy of itself before the increment runs, so
+    /// every iteration has its own loop variables and closures created by the previous iteration
+    /// keep the values they saw.
+    fn renew_loop_scope(&self) {
+        let mut env_stack = self.environment_stack.borrow_mut();
+        let scope = env_stack.last_mut().unwrap();
+        let copy = scope.borrow().clone();
+        *scope = Rc::new(RefCell::new(copy));
+    }
+
+    /// Runs `f` with a new environment, enclosed by the current one, on top of the stack.
+    fn in_new_scope<T>(&self, f: impl FnOnce() -> InterpreterResult<T>) -> InterpreterResult<T> {
+        let env = Environment::new_enclosed(self.current_environment());
+        self.environment_stack
+            .borrow_mut()
+            .push(Rc::new(RefCell::new(env)));
+
+        let result = f();
+        self.environment_stack.borrow_mut().pop();
+
+        result
+    }
+
+    fn current_environment(&self) -> RcEnvironment {
+        let env_stack = self.environment_stack.borrow();
+        env_stack.last().unwrap().clone()
+    }
+
+    fn write_output(&self, args: std::fmt::Arguments) {
+        // Just like println!, a failure to write the output is not recoverable
+        self.output
+            .borrow_mut()
+            .write_fmt(args)
+            .expect("Failed to write the interpreter output");
+    }
+
+    fn flush_output(&self) {
+        self.output
+            .borrow_mut()
+            .flush()
+            .expect("Failed to write the interpreter output");
+    }
+
+    fn write_error_output(&self, args: std::fmt::Arguments) {
+        let mut error_output = self.error_output.borrow_mut();
+        error_output
+            .write_fmt(args)
+            .and_then(|()| error_output.flush())
+            .expect("Failed to write the interpreter error output");
+    }
+
+    /// Reads a line from the interpreter input, without its trailing newline.
+    fn read_input_line(&self) -> std::io::Result<String> {
+        let mut line = String::new();
+        match self.input.borrow_mut().as_mut() {
+            Some(input) => input.read_line(&mut line)?,
+            None => std::io::stdin().read_line(&mut line)?,
+        };
+
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(line)
+    }
+
+    /// Evaluates the superclass of a class declaration, raising an error if it is not a class.
+    fn validate_superclass(&self, expr: &Expression) -> InterpreterResult<LoxValue> {
+        let value = self.evaluate(expr)?;
+        if value.as_class().is_some() {
+            return Ok(value);
+        }
+
+        let Expression::Var(variable, _) = expr else {
+            unreachable!("Super classes are always parsed as variables")
+        };
+        interpreter_error!(
+            InterpreterErrorType::InvalidSuperClass,
+            variable.token.clone()
+        )
+    }
+
+    fn execute_try(
+        &self,
+        body: &[Statement],
+        catch_name: &Token,
+        catch_body: &[Statement],
+        inside_loop: bool,
+    ) -> InterpreterResult<ControlFlow> {
+        let body_env = Environment::new_enclosed(self.current_environment());
+        let thrown = match self.execute_block(body, Rc::new(RefCell::new(body_env)), inside_loop) {
+            Ok(ControlFlow::Throw(value, _)) => value,
+            Ok(flow) => return Ok(flow),
+            Err(error) => match *error {
+                InterpreterError {
+                    error_type: InterpreterErrorType::Thrown(value),
+                    ..
+                } => value,
+                error => return Err(Box::new(error)),
             },
-            _ => panic!("Super class must be a class"),
+        };
+
+        let mut catch_env = Environment::new_enclosed(self.current_environment());
+        catch_env.define(catch_name.lexeme().to_string(), thrown);
+        self.execute_block(catch_body, Rc::new(RefCell::new(catch_env)), inside_loop)
+    }
+
+    /// Calls the `close` method of the resource of a `with` statement. Values that are not
+    /// instances, or have no such method, are left alone.
+    fn close_resource(&self, resource: &LoxValue, keyword: &Token) -> InterpreterResult<()> {
+        if let LoxValue::Instance(instance) = resource
+            && let Some(method) = instance.find_method("close")
+        {
+            let close = self.bind_method(instance.clone(), method);
+            self.interpret_call(close, Vec::new(), keyword)?;
         }
+        Ok(())
     }
 
     fn execute_block(
@@ -270,7 +767,7 @@ impl Interpreter {
         env: Rc<RefCell<Environment>>,
         inside_loop: bool,
     ) -> InterpreterResult<ControlFlow> {
-        for statement in statements {
+        for statement in hoisted(statements) {
             {
                 let mut env_mut = self.environment_stack.borrow_mut();
                 env_mut.push(env.clone());
@@ -284,6 +781,9 @@ impl Interpreter {
                 ControlFlow::BreakLoop => return Ok(ControlFlow::BreakLoop),
                 ControlFlow::ContinueLoop => return Ok(ControlFlow::ContinueLoop),
                 ControlFlow::Return(val) => return Ok(ControlFlow::Return(val)),
+                ControlFlow::Throw(value, keyword) => {
+                    return Ok(ControlFlow::Throw(value, keyword));
+                }
             }
         }
 
@@ -292,32 +792,94 @@ impl Interpreter {
 
     fn evaluate(&self, expression: &Expression) -> InterpreterResult<LoxValue> {
         match expression {
-            Expression::True => Ok(LoxValue::Boolean(true)),
-            Expression::False => Ok(LoxValue::Boolean(false)),
-            Expression::Number(num) => Ok(LoxValue::Number(**num)),
-            Expression::String(str) => Ok(LoxValue::String(Rc::new(str.to_string()))),
-            Expression::Nil => Ok(LoxValue::Nil),
-            Expression::Grouping(expr) => self.evaluate(expr),
-            Expression::Unary(token, expression) => self.evaluate_unary(token, expression),
+            Expression::True(_) => Ok(LoxValue::Boolean(true)),
+            Expression::False(_) => Ok(LoxValue::Boolean(false)),
+            Expression::Number(num, ..) => Ok(LoxValue::Number(**num)),
+            Expression::String(str, _) => Ok(LoxValue::String(Rc::new(str.to_string()))),
+            Expression::Nil(_) => Ok(LoxValue::Nil),
+            Expression::Ternary {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => {
+                if self.evaluate(condition)?.is_truthy() {
+                    self.evaluate(then_expr)
+                } else {
+                    self.evaluate(else_expr)
+                }
+            }
+            Expression::Comma(expressions, _) => {
+                let mut value = LoxValue::Nil;
+                for expression in expressions {
+                    value = self.evaluate(expression)?;
+                }
+                Ok(value)
+            }
+            Expression::Block(statements, value, _) => self.in_new_scope(|| {
+                for statement in hoisted(statements) {
+                    match self.execute_statement(statement, false)? {
+                        ControlFlow::Normal => {}
+                        ControlFlow::Throw(value, keyword) => {
+                            return interpreter_error!(
+                                InterpreterErrorType::Thrown(value),
+                                keyword
+                            );
+                        }
+                        /* The resolver rejects return, and break/continue require a loop */
+                        _ => unreachable!("Control flow cannot escape a block expression"),
+                    }
+                }
+                self.evaluate(value)
+            }),
+            Expression::Lambda {
+                parameters, body, ..
+            } => {
+                let lambda = Callable::LoxFunction(LoxFunction {
+                    closure: self.current_environment(),
+                    name: String::from("lambda"),
+                    is_initializer: false,
+                    params: parameters.clone(),
+                    block: body.clone(),
+                });
+                Ok(LoxValue::Callable(Rc::new(lambda)))
+            }
+            Expression::Grouping(expr, _) => self.evaluate(expr),
+            Expression::Unary(token, expression, _) => self.evaluate_unary(token, expression),
+            Expression::Prefix {
+                operator, target, ..
+            } => {
+                let (_, updated) = self.evaluate_increment(target, operator)?;
+                Ok(updated)
+            }
+            Expression::Postfix {
+                target, operator, ..
+            } => {
+                let (previous, _) = self.evaluate_increment(target, operator)?;
+                Ok(previous)
+            }
             Expression::Binary {
                 left,
                 operator,
                 right,
+                ..
             } => self.evaluate_binary(left, operator, right),
-            Expression::Var(variable) => {
+            Expression::Var(variable, _) => {
                 let name = variable.token.lexeme();
                 let value = match self.lookup_variable(name, expression) {
                     Some(value) => value,
-                    None => {
-                        return interpreter_error!(
-                            InterpreterErrorType::UndefinedVariable(name.to_string()),
-                            variable.token.clone()
-                        );
-                    }
+                    None => return self.undefined_variable(name, &variable.token),
                 };
                 Ok(value.clone())
             }
-            Expression::This { keyword } => {
+            Expression::This { keyword, .. } => {
+                /* The innermost running function is the one `this` appears in. If it is a
+                 * bound method, its instance is `this`. Otherwise `this` was captured by a
+                 * closure, and it is looked up like any other variable. */
+                if let Some(Some(instance)) = self.this_stack.borrow().last() {
+                    return Ok(LoxValue::Instance(instance.clone()));
+                }
+
                 match self.lookup_variable(keyword.lexeme(), expression) {
                     Some(value) => Ok(value),
                     None => interpreter_error!(
@@ -326,32 +888,20 @@ impl Interpreter {
                     ),
                 }
             }
-            Expression::Super { keyword: _ } => todo!(),
-            Expression::Assignment { name, value, token } => {
-                let distance = match self.locals.borrow().get(value) {
-                    Some(distance) => *distance,
-                    None => todo!(),
-                };
-
-                let last_env = {
-                    let env_stack = self.environment_stack.borrow();
-                    env_stack.last().unwrap().clone()
-                };
-
+            Expression::Super { .. } => {
+                unreachable!("The resolver only allows super in property accesses")
+            }
+            Expression::Assignment {
+                name, value, token, ..
+            } => {
                 let value = self.evaluate(value)?;
 
-                if !last_env
-                    .borrow_mut()
-                    .assign_at(name, value.clone(), distance)
-                {
-                    return interpreter_error!(
-                        InterpreterErrorType::UndefinedVariable(String::from(name)),
-                        token.clone()
-                    );
+                if !self.assign_variable(name, expression, value.clone()) {
+                    return self.undefined_variable(name, token);
                 }
                 Ok(value)
             }
-            Expression::Or { left, right } => {
+            Expression::Or { left, right, .. } => {
                 let left = self.evaluate(left)?;
                 if left.is_truthy() {
                     Ok(left)
@@ -359,7 +909,7 @@ impl Interpreter {
                     self.evaluate(right)
                 }
             }
-            Expression::And { left, right } => {
+            Expression::And { left, right, .. } => {
                 let left = self.evaluate(left)?;
                 if !left.is_truthy() {
                     Ok(left)
@@ -371,15 +921,43 @@ impl Interpreter {
                 callee,
                 paren,
                 args,
+                ..
             } => {
-                let function = match self.evaluate(callee)? {
-                    LoxValue::Callable(callable) => callable,
-                    _ => {
-                        return interpreter_error!(
-                            InterpreterErrorType::NotACallable,
-                            paren.clone()
-                        );
+                let function = match &**callee {
+                    Expression::Get {
+                        expression, token, ..
+                    } if !matches!(**expression, Expression::Super { .. }) => {
+                        let object = self.evaluate(expression)?;
+                        match self.get_property(&object, token)? {
+                            LoxValue::Callable(callable) => callable,
+                            value => {
+                                let owner = match (&object, object.as_class()) {
+                                    (_, Some(class)) => class.to_string(),
+                                    (LoxValue::Instance(instance), _) => {
+                                        instance.class_name().to_string()
+                                    }
+                                    _ => object.type_name().to_string(),
+                                };
+                                return interpreter_error!(
+                                    InterpreterErrorType::NotACallableProperty {
+                                        owner,
+                                        field: token.lexeme().to_string(),
+                                        type_name: value.type_name(),
+                                    },
+                                    token.clone()
+                                );
+                            }
+                        }
                     }
+                    callee => match self.evaluate(callee)? {
+                        LoxValue::Callable(callable) => callable,
+                        _ => {
+                            return interpreter_error!(
+                                InterpreterErrorType::NotACallable,
+                                paren.clone()
+                            );
+                        }
+                    },
                 };
 
                 let mut arguments = Vec::new();
@@ -389,75 +967,225 @@ impl Interpreter {
 
                 self.interpret_call(function, arguments, paren)
             }
-            Expression::Get { expression, token } => {
-                let result = self.evaluate(expression)?;
-
-                match result {
-                    LoxValue::Instance(instance) => match instance.get(token.lexeme()) {
-                        Field::Value(value) => Ok(value),
-                        Field::Method(method) => {
-                            let bound_method = self.bind_method(instance.clone(), method.clone());
-                            Ok(LoxValue::Callable(bound_method))
-                        }
-                        Field::Undefined => interpreter_error!(
-                            InterpreterErrorType::NotAProperty {
-                                class_name: instance.class_name().to_string(),
-                                field: token.lexeme().to_string()
-                            },
-                            token.clone()
-                        ),
-                    },
-                    _ => {
-                        interpreter_error!(
-                            InterpreterErrorType::InvalidInstance(token.lexeme().to_string()),
-                            token.clone()
-                        )
-                    }
+            Expression::Get {
+                expression, token, ..
+            } => {
+                if let Expression::Super { .. } = &**expression {
+                    return self.get_super_method(expression, token);
                 }
+
+                let object = self.evaluate(expression)?;
+                self.get_property(&object, token)
+            }
+            Expression::Index {
+                object,
+                bracket,
+                index,
+                ..
+            } => {
+                let object = self.evaluate(object)?;
+                let index = self.evaluate(index)?;
+                Self::index(object, index, bracket)
             }
             Expression::Set {
                 name,
                 object,
                 value,
+                ..
             } => {
-                if let LoxValue::Instance(instance) = self.evaluate(object)? {
-                    let value = self.evaluate(value)?;
-                    instance.set(name.lexeme(), value.clone());
-                    Ok(value)
-                } else {
-                    // TODO: This should have better formatting
-                    interpreter_error!(
-                        InterpreterErrorType::InvalidInstance(format!("{object:?}")),
-                        name.clone()
-                    )
+                let target = self.evaluate(object)?;
+                self.set_property(target, object, name, value)
+            }
+            Expression::LogicalSet {
+                target,
+                operator,
+                value,
+                ..
+            } => {
+                let Expression::Get {
+                    expression: object,
+                    token: name,
+                    ..
+                } = &**target
+                else {
+                    unreachable!("The parser only desugars properties into logical sets")
+                };
+
+                /* The object is evaluated once, both to read the field and to assign it */
+                let target = self.evaluate(object)?;
+                let current = self.get_property(&target, name)?;
+                let assign = match operator.token_type() {
+                    TokenType::AndEqual => current.is_truthy(),
+                    _ => !current.is_truthy(),
+                };
+
+                if !assign {
+                    return Ok(current);
                 }
+                self.set_property(target, object, name, value)
             }
         }
     }
 
-    fn interpret_call(
+    /// Evaluates `value` and assigns it to the field `name` of `target`, which is the value of
+    /// `object`. Static fields are set on classes, and regular fields on instances.
+    fn set_property(
         &self,
-        function: Rc<Callable>,
-        arguments: Vec<LoxValue>,
-        paren: &Token,
+        target: LoxValue,
+        object: &Expression,
+        name: &Token,
+        value: &Expression,
     ) -> InterpreterResult<LoxValue> {
-        match &*function {
-            Callable::Native { func, arity } => {
-                self.evaluate_native(paren, *arity, func, &arguments)
+        if let Some(class) = target.as_class() {
+            let value = self.evaluate(value)?;
+            if !class.set_static(name.lexeme(), value.clone()) {
+                return interpreter_error!(
+                    InterpreterErrorType::NotAStaticField {
+                        class_name: class.to_string(),
+                        field: name.lexeme().to_string()
+                    },
+                    name.clone()
+                );
+            }
+            Ok(value)
+        } else if let LoxValue::Instance(instance) = target {
+            let value = self.evaluate(value)?;
+            if !instance.set(name.lexeme(), value.clone()) {
+                return interpreter_error!(
+                    InterpreterErrorType::FrozenInstance {
+                        class_name: instance.class_name().to_string(),
+                        field: name.lexeme().to_string()
+                    },
+                    name.clone()
+                );
+            }
+            Ok(value)
+        } else {
+            // TODO: This should have better formatting
+            interpreter_error!(
+                InterpreterErrorType::InvalidInstance(format!("{object:?}")),
+                name.clone()
+            )
+        }
+    }
+
+    /// Looks up a method of the superclass for `super.name`, bound to the instance of the
+    /// running method. Overrides in the class of the instance are skipped.
+    fn get_super_method(
+        &self,
+        super_expr: &Expression,
+        name: &Token,
+    ) -> InterpreterResult<LoxValue> {
+        /* `this` is bound in the environment right inside the one that holds `super` */
+        let distance = self.locals.borrow().get(super_expr).copied();
+        let environment = self.current_environment();
+        let bindings = distance.and_then(|distance| {
+            let environment = environment.borrow();
+            let super_class = environment.get_at("super", distance)?;
+            let this = environment.get_at("this", distance.checked_sub(1)?)?;
+            Some((super_class, this))
+        });
+
+        let Some((super_class, LoxValue::Instance(instance))) = bindings else {
+            return self.undefined_variable("super", name);
+        };
+        let Some(class) = super_class.as_class() else {
+            unreachable!("super is always bound to a class")
+        };
+
+        match class.find_method(name.lexeme()) {
+            Some(method) => Ok(LoxValue::Callable(self.bind_method(instance, method))),
+            None => interpreter_error!(
+                InterpreterErrorType::NotAProperty {
+                    class_name: class.to_string(),
+                    field: name.lexeme().to_string()
+                },
+                name.clone()
+            ),
+        }
+    }
+
+    /// Looks up the property named by `token` on a value, binding methods to their instance.
+    fn get_property(&self, object: &LoxValue, token: &Token) -> InterpreterResult<LoxValue> {
+        if let Some(class) = object.as_class() {
+            return match class.get_static(token.lexeme()) {
+                Some(value) => Ok(value),
+                None => interpreter_error!(
+                    InterpreterErrorType::NotAStaticField {
+                        class_name: class.to_string(),
+                        field: token.lexeme().to_string()
+                    },
+                    token.clone()
+                ),
+            };
+        }
+
+        match object {
+            LoxValue::Instance(instance) => match instance.get(token.lexeme()) {
+                Field::Value(value) => Ok(value),
+                Field::Method(method) => {
+                    let bound_method = self.bind_method(instance.clone(), method.clone());
+                    Ok(LoxValue::Callable(bound_method))
+                }
+                Field::Undefined => interpreter_error!(
+                    InterpreterErrorType::NotAProperty {
+                        class_name: instance.class_name().to_string(),
+                        field: token.lexeme().to_string()
+                    },
+                    token.clone()
+                ),
+            },
+            LoxValue::String(string) => match token.lexeme() {
+                "length" => Ok(LoxValue::Number(string.chars().count() as f64)),
+                field => interpreter_error!(
+                    InterpreterErrorType::NotABuiltinProperty {
+                        type_name: "string",
+                        field: field.to_string()
+                    },
+                    token.clone()
+                ),
+            },
+            LoxValue::Bytes(bytes) => match token.lexeme() {
+                "length" => Ok(LoxValue::Number(bytes.len() as f64)),
+                field => interpreter_error!(
+                    InterpreterErrorType::NotABuiltinProperty {
+                        type_name: "bytes",
+                        field: field.to_string()
+                    },
+                    token.clone()
+                ),
+            },
+            _ => {
+                interpreter_error!(
+                    InterpreterErrorType::InvalidInstance(token.lexeme().to_string()),
+                    token.clone()
+                )
+            }
+        }
+    }
+
+    fn interpret_call(
+        &self,
+        function: Rc<Callable>,
+        arguments: Vec<LoxValue>,
+        paren: &Token,
+    ) -> InterpreterResult<LoxValue> {
+        match &*function {
+            Callable::Native { func, arity } => {
+                self.evaluate_native(paren, *arity, func, &arguments)
+            }
+            Callable::NativeMethod { func, arity } => {
+                self.check_arity(paren, *arity, arguments.len())?;
+                func(self, &arguments, paren)
             }
             Callable::LoxFunction(function) => {
                 self.evaluate_lox_function(paren, arguments, function)
             }
+            Callable::Bound { method, receiver } => {
+                self.evaluate_lox_function(paren, arguments, &method.bind(receiver.clone()))
+            }
             Callable::Constructor { class, arity } => {
-                if *arity != arguments.len() {
-                    return interpreter_error!(
-                        InterpreterErrorType::WrongArity {
-                            original: 0,
-                            user: arguments.len()
-                        },
-                        paren.clone()
-                    );
-                }
+                self.check_arity(paren, *arity, arguments.len())?;
                 let instance = Rc::new(value::Instance::new(class.clone()));
                 if let Some(initializer) = class.find_method("init") {
                     let initializer = self.bind_method(instance.clone(), initializer);
@@ -470,26 +1198,36 @@ impl Interpreter {
 
     fn bind_method(&self, instance: Rc<value::Instance>, method: Rc<Callable>) -> Rc<Callable> {
         if let Callable::LoxFunction(function) = &*method {
-            Rc::new(Callable::LoxFunction(function.bind(instance)))
+            Rc::new(Callable::Bound {
+                method: Rc::new(function.clone()),
+                receiver: instance,
+            })
         } else {
             method
         }
     }
 
     fn lookup_variable(&self, name: &str, expression: &Expression) -> Option<LoxValue> {
-        let locals = self.locals.borrow();
-        match locals.get(expression) {
-            Some(distance) => {
-                let last_env = {
-                    let env_stack = self.environment_stack.borrow();
-                    env_stack.last().unwrap().clone()
-                };
-                last_env.borrow().get_at(name, *distance)
-            }
+        let distance = self.locals.borrow().get(expression).copied();
+        match distance {
+            Some(distance) => self.current_environment().borrow().get_at(name, distance),
             None => self.globals.borrow().get(name),
         }
     }
 
+    /// Assigns to an existing variable, using the scope depth the resolver found for
+    /// `expression`. Returns false if the variable is not defined.
+    fn assign_variable(&self, name: &str, expression: &Expression, value: LoxValue) -> bool {
+        let distance = self.locals.borrow().get(expression).copied();
+        match distance {
+            Some(distance) => self
+                .current_environment()
+                .borrow_mut()
+                .assign_at(name, value, distance),
+            None => self.globals.borrow_mut().assign_at(name, value, 0),
+        }
+    }
+
     fn evaluate_lox_function(
         &self,
         token: &Token,
@@ -498,29 +1236,26 @@ impl Interpreter {
     ) -> InterpreterResult<LoxValue> {
         let mut function_env = Environment::new_enclosed(function.closure.clone());
 
-        if function.params.len() != arguments.len() {
-            return interpreter_error!(
-                InterpreterErrorType::WrongArity {
-                    original: function.params.len(),
-                    user: arguments.len()
-                },
-                token.clone()
-            );
-        }
+        self.check_arity(token, Arity::Fixed(function.params.len()), arguments.len())?;
 
         for (i, arg) in arguments.into_iter().enumerate() {
             function_env.define(function.params[i].lexeme().to_string(), arg);
         }
 
-        let value = match self.execute_block(
-            &function.block,
-            Rc::new(RefCell::new(function_env)),
-            false,
-        )? {
+        self.this_stack.borrow_mut().push(function.bound_instance());
+        let result =
+            self.execute_block(&function.block, Rc::new(RefCell::new(function_env)), false);
+        self.this_stack.borrow_mut().pop();
+
+        let value = match result? {
+            /* Throws travel as errors through expressions, until a `try` catches them */
+            ControlFlow::Throw(value, keyword) => {
+                return interpreter_error!(InterpreterErrorType::Thrown(value), keyword);
+            }
             _ if function.is_initializer => function
                 .closure
                 .borrow()
-                .get_at("init", 0)
+                .get_at("this", 0)
                 .unwrap_or(LoxValue::Nil),
             ControlFlow::Normal => LoxValue::Nil,
             ControlFlow::BreakLoop => LoxValue::Nil,
@@ -534,23 +1269,185 @@ impl Interpreter {
     fn evaluate_native(
         &self,
         token: &Token,
-        arity: usize,
+        arity: Arity,
         func: &NativeFunc,
         arguments: &[LoxValue],
     ) -> InterpreterResult<LoxValue> {
-        if arity != arguments.len() {
+        self.check_arity(token, arity, arguments.len())?;
+
+        match func(arguments) {
+            Ok(result) => Ok(result),
+            Err(e) => interpreter_error!(InterpreterErrorType::Native(e), token.clone()),
+        }
+    }
+
+    fn check_arity(&self, token: &Token, arity: Arity, count: usize) -> InterpreterResult<()> {
+        if !arity.accepts(count) {
             return interpreter_error!(
                 InterpreterErrorType::WrongArity {
                     original: arity,
-                    user: arguments.len()
+                    user: count
                 },
                 token.clone()
             );
         }
+        Ok(())
+    }
 
-        match func(arguments) {
-            Ok(result) => Ok(result),
-            Err(e) => interpreter_error!(InterpreterErrorType::Native(e), token.clone()),
+    /// Converts a value into its string form. Instances may customize it by declaring a
+    /// `toString` method without parameters.
+    pub fn stringify(&self, value: &LoxValue, token: &Token) -> InterpreterResult<String> {
+        if let LoxValue::Instance(instance) = value
+            && let Some(method) = instance.find_method("toString")
+            && method.arity() == Arity::Fixed(0)
+        {
+            let method = self.bind_method(instance.clone(), method);
+            let result = self.interpret_call(method, Vec::new(), token)?;
+            return Ok(result.to_string());
+        }
+
+        Ok(value.to_string())
+    }
+
+    /// Returns a debugging representation of the value: strings are quoted and escaped, and
+    /// instances show their class and fields, as in `Point(x: 1, y: "a")`. Unlike
+    /// [`Interpreter::stringify`], it never calls `toString`.
+    pub fn reprify(&self, value: &LoxValue) -> String {
+        self.reprify_visiting(value, &mut Vec::new())
+    }
+
+    /// `visiting` holds the instances being shown further up, which are shown as `...` if they
+    /// are found again so reference cycles do not recurse forever.
+    fn reprify_visiting(&self, value: &LoxValue, visiting: &mut Vec<*const Instance>) -> String {
+        match value {
+            LoxValue::String(string) => syntax::quote(string),
+            LoxValue::Instance(instance) => {
+                if visiting.contains(&Rc::as_ptr(instance)) {
+                    return String::from("...");
+                }
+
+                visiting.push(Rc::as_ptr(instance));
+                let fields: Vec<String> = instance
+                    .iter_fields()
+                    .map(|(name, value)| {
+                        format!("{name}: {}", self.reprify_visiting(&value, visiting))
+                    })
+                    .collect();
+                visiting.pop();
+
+                format!("{}({})", instance.class_name(), fields.join(", "))
+            }
+            other => other.to_string(),
+        }
+    }
+
+    /// Increments or decrements the variable or property in `target`, depending on the
+    /// operator. Returns both the previous and the updated value.
+    fn evaluate_increment(
+        &self,
+        target: &Expression,
+        operator: &Token,
+    ) -> InterpreterResult<(LoxValue, LoxValue)> {
+        let step = |value: LoxValue| match (operator.token_type(), value) {
+            (TokenType::PlusPlus, LoxValue::Number(n)) => Ok(LoxValue::Number(n + 1.0)),
+            (TokenType::MinusMinus, LoxValue::Number(n)) => Ok(LoxValue::Number(n - 1.0)),
+            (op, value) => interpreter_error!(
+                InterpreterErrorType::WrongUnaryOperands(op.clone(), value),
+                operator.clone()
+            ),
+        };
+
+        match target {
+            Expression::Var(variable, _) => {
+                let name = variable.token.lexeme();
+                let Some(previous) = self.lookup_variable(name, target) else {
+                    return self.undefined_variable(name, &variable.token);
+                };
+
+                let updated = step(previous.clone())?;
+                self.assign_variable(name, target, updated.clone());
+                Ok((previous, updated))
+            }
+            Expression::Get {
+                expression, token, ..
+            } => {
+                let target = self.evaluate(expression)?;
+                if let Some(class) = target.as_class() {
+                    let not_a_static_field = || {
+                        interpreter_error!(
+                            InterpreterErrorType::NotAStaticField {
+                                class_name: class.to_string(),
+                                field: token.lexeme().to_string()
+                            },
+                            token.clone()
+                        )
+                    };
+
+                    let Some(previous) = class.get_static(token.lexeme()) else {
+                        return not_a_static_field();
+                    };
+                    let updated = step(previous.clone())?;
+                    if !class.set_static(token.lexeme(), updated.clone()) {
+                        return not_a_static_field();
+                    }
+                    return Ok((previous, updated));
+                }
+
+                let LoxValue::Instance(instance) = target else {
+                    return interpreter_error!(
+                        InterpreterErrorType::InvalidInstance(token.lexeme().to_string()),
+                        token.clone()
+                    );
+                };
+
+                let previous = match instance.get(token.lexeme()) {
+                    Field::Value(value) => value,
+                    Field::Method(method) => LoxValue::Callable(method),
+                    Field::Undefined => {
+                        return interpreter_error!(
+                            InterpreterErrorType::NotAProperty {
+                                class_name: instance.class_name().to_string(),
+                                field: token.lexeme().to_string()
+                            },
+                            token.clone()
+                        );
+                    }
+                };
+
+                let updated = step(previous.clone())?;
+                if !instance.set(token.lexeme(), updated.clone()) {
+                    return interpreter_error!(
+                        InterpreterErrorType::FrozenInstance {
+                            class_name: instance.class_name().to_string(),
+                            field: token.lexeme().to_string()
+                        },
+                        token.clone()
+                    );
+                }
+                Ok((previous, updated))
+            }
+            _ => unreachable!("The parser only accepts variables and properties as targets"),
+        }
+    }
+
+    /// Reads `object[index]`. Only bytes can be indexed, which gives the byte at the index.
+    fn index(object: LoxValue, index: LoxValue, bracket: &Token) -> InterpreterResult<LoxValue> {
+        let LoxValue::Bytes(bytes) = object else {
+            return interpreter_error!(
+                InterpreterErrorType::NotIndexable(object.type_name()),
+                bracket.clone()
+            );
+        };
+
+        match index {
+            LoxValue::Number(n) if let Some(byte) = native::byte(&bytes, n) => Ok(byte),
+            index => interpreter_error!(
+                InterpreterErrorType::InvalidIndex {
+                    index,
+                    length: bytes.len()
+                },
+                bracket.clone()
+            ),
         }
     }
 
@@ -562,6 +1459,8 @@ impl Interpreter {
         match (token.token_type(), self.evaluate(expression)?) {
             /* Numerical negation */
             (TokenType::Minus, LoxValue::Number(num)) => Ok(LoxValue::Number(-num)),
+            /* Unary plus leaves numbers as they are, and rejects anything else */
+            (TokenType::Plus, LoxValue::Number(num)) => Ok(LoxValue::Number(num)),
 
             /* Boolean negation */
             (TokenType::Bang, LoxValue::Boolean(value)) => Ok(LoxValue::Boolean(!value)),
@@ -579,6 +1478,16 @@ impl Interpreter {
         }
     }
 
+    fn arithmetic_result(&self, result: f64, operator: &Token) -> LoxValue {
+        if self.warn_precision && result.is_finite() && result.abs() > MAX_SAFE_INTEGER {
+            self.write_error_output(format_args!(
+                "[line {}] Warning: {result} is beyond 2^53, where integers lose precision\n",
+                operator.line()
+            ));
+        }
+        LoxValue::Number(result)
+    }
+
     fn evaluate_binary(
         &self,
         first_operand: &Expression,
@@ -592,27 +1501,35 @@ impl Interpreter {
         ) {
             /* Algebraic operations */
             (LoxValue::Number(a), TokenType::Plus, LoxValue::Number(b)) => {
-                Ok(LoxValue::Number(a + b))
+                Ok(self.arithmetic_result(a + b, operator))
             }
             (LoxValue::Number(a), TokenType::Minus, LoxValue::Number(b)) => {
-                Ok(LoxValue::Number(a - b))
+                Ok(self.arithmetic_result(a - b, operator))
             }
             (LoxValue::Number(a), TokenType::Star, LoxValue::Number(b)) => {
-                Ok(LoxValue::Number(a * b))
+                Ok(self.arithmetic_result(a * b, operator))
             }
 
             /* Handle division by zero */
-            (LoxValue::Number(_), TokenType::Slash, LoxValue::Number(0f64)) => {
+            (LoxValue::Number(_), TokenType::Slash, LoxValue::Number(0f64))
+                if !self.ieee_division =>
+            {
                 interpreter_error!(InterpreterErrorType::DivisionByZero, operator.clone())
             }
             (LoxValue::Number(a), TokenType::Slash, LoxValue::Number(b)) => {
-                Ok(LoxValue::Number(a / b))
+                Ok(self.arithmetic_result(a / b, operator))
             }
 
-            /* Logical comparisons */
+            /*
+             * Logical comparisons. Numbers follow IEEE-754, so any comparison against NaN is
+             * false, except for `!=`, and `nan == nan` is false too. Scripts can use `is_nan`.
+             */
             (LoxValue::Number(a), TokenType::EqualEqual, LoxValue::Number(b)) => {
                 Ok(LoxValue::Boolean(a == b))
             }
+            (LoxValue::Number(a), TokenType::BangEqual, LoxValue::Number(b)) => {
+                Ok(LoxValue::Boolean(a != b))
+            }
             (LoxValue::Number(a), TokenType::GreaterEqual, LoxValue::Number(b)) => {
                 Ok(LoxValue::Boolean(a >= b))
             }
@@ -635,6 +1552,26 @@ impl Interpreter {
             (LoxValue::String(s1), TokenType::Plus, any) => {
                 Ok(LoxValue::String(Rc::new(format!("{s1}{any}"))))
             }
+            (LoxValue::String(s), TokenType::Star, LoxValue::Number(n))
+            | (LoxValue::Number(n), TokenType::Star, LoxValue::String(s)) => {
+                /* The count is truncated, so "ab" * 2.7 == "abab" */
+                let too_long = || {
+                    s.len()
+                        .checked_mul(n as usize)
+                        .is_none_or(|length| length > MAX_REPEATED_LENGTH)
+                };
+                if n < 0.0 || !n.is_finite() || too_long() {
+                    return interpreter_error!(
+                        InterpreterErrorType::InvalidRepetitionCount(n),
+                        operator.clone()
+                    );
+                }
+                Ok(LoxValue::String(Rc::new(s.repeat(n as usize))))
+            }
+
+            /* Values of any other type are compared by value, or by identity for objects */
+            (a, TokenType::EqualEqual, b) => Ok(LoxValue::Boolean(a == b)),
+            (a, TokenType::BangEqual, b) => Ok(LoxValue::Boolean(a != b)),
 
             /* Any other invalid operation will be handled here. */
             (t1, op, t2) => interpreter_error!(
@@ -650,7 +1587,7 @@ impl Interpreter {
         macro_rules! define_native {
             ($name: literal, $arity: expr, $fun: expr) => {{
                 let func = Callable::Native {
-                    arity: $arity,
+                    arity: Arity::Fixed($arity),
                     func: $fun,
                 };
                 _global.define(String::from($name), LoxValue::Callable(Rc::new(func)));
@@ -658,8 +1595,1837 @@ impl Interpreter {
         }
 
         define_native!("clock", 0, native::clock);
-        define_native!("read_line", 0, native::read_line);
         define_native!("random", 2, native::random);
         define_native!("string_to_number", 1, native::string_to_number);
+        define_native!("parse_number", 1, native::parse_number);
+        define_native!("between", 3, native::between);
+        define_native!("mod", 2, native::modulo);
+        define_native!("round_to", 2, native::round_to);
+        define_native!("to_fixed", 2, native::to_fixed);
+        define_native!("to_exponential", 2, native::to_exponential);
+        define_native!("to_hex", 1, native::to_hex);
+        define_native!("from_hex", 1, native::from_hex);
+        define_native!("byte_at", 2, native::byte_at);
+        define_native!("read_file_bytes", 1, native::read_file_bytes);
+        define_native!("hash", 1, native::hash);
+        define_native!("is_nan", 1, native::is_nan);
+        define_native!("inf", 0, native::inf);
+        define_native!("nan", 0, native::nan);
+        define_native!("chr", 1, native::chr);
+        define_native!("ord", 1, native::ord);
+        define_native!("sleep", 1, native::sleep);
+        define_native!("freeze", 1, native::freeze);
+        define_native!("fields", 1, native::fields);
+        define_native!("deep_equals", 2, native::deep_equals);
+
+        macro_rules! define_native_method {
+            ($name: literal, $minimum: literal.., $fun: expr) => {{
+                let func = Callable::NativeMethod {
+                    arity: Arity::AtLeast($minimum),
+                    func: $fun,
+                };
+                _global.define(String::from($name), LoxValue::Callable(Rc::new(func)));
+            }};
+            ($name: literal, $arity: expr, $fun: expr) => {{
+                let func = Callable::NativeMethod {
+                    arity: Arity::Fixed($arity),
+                    func: $fun,
+                };
+                _global.define(String::from($name), LoxValue::Callable(Rc::new(func)));
+            }};
+        }
+
+        define_native_method!("read_line", 0, native::read_line);
+        define_native_method!("input", 1, native::input);
+        define_native_method!("to_string", 1, native::to_string);
+        define_native_method!("repr", 1, native::repr);
+        define_native_method!("eprint", 1, native::eprint);
+        define_native_method!("eprintln", 1, native::eprintln);
+        define_native_method!("debug_env", 0, native::debug_env);
+        define_native_method!("exit", 1, native::exit);
+        define_native_method!("assert_eq", 2, native::assert_eq);
+        define_native_method!("assert_throws", 1, native::assert_throws);
+        define_native_method!("format", 1.., native::format);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Interpreter, InterpreterErrorType, InterpreterResult, LoxValue, NativeError, PRELUDE,
+        PreludeError,
+    };
+    use crate::resolver::Resolver;
+    use std::cell::RefCell;
+    use std::io::{Cursor, Write};
+    use std::process::ExitCode;
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl SharedBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn captured_interpreter() -> (Interpreter, SharedBuffer) {
+        let output = SharedBuffer::default();
+        (Interpreter::with_output(output.clone()), output)
+    }
+
+    fn try_run(interpreter: &Interpreter, source: &str) -> InterpreterResult<Option<ExitCode>> {
+        let tokens = syntax::Scanner::new(Cursor::new(source))
+            .scan_tokens()
+            .unwrap();
+        let statements = syntax::Parser::new(&tokens).statements().unwrap();
+        Resolver::new(interpreter)
+            .resolve_statements(&statements)
+            .unwrap();
+        interpreter.interpret(&statements)
+    }
+
+    fn run(interpreter: &Interpreter, source: &str) {
+        try_run(interpreter, source).unwrap();
+    }
+
+    #[test]
+    fn trace_logs_statements_and_values() {
+        let (mut interpreter, output) = captured_interpreter();
+        interpreter.set_trace(true);
+
+        run(&interpreter, "print 1 + 2;\n3 * 4;");
+
+        assert_eq!(
+            output.contents(),
+            "[line 1] print (+ 1 2);\n3\n[line 2] (* 3 4);\n=> 12\n"
+        );
+    }
+
+    #[test]
+    fn trace_is_disabled_by_default() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(&interpreter, "print 1 + 2;\n3 * 4;");
+
+        assert_eq!(output.contents(), "3\n");
+    }
+
+    #[test]
+    fn ternary_only_evaluates_one_branch() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "print true ? 1 : undefined_variable;\nprint nil ? undefined_variable : 2;",
+        );
+
+        assert_eq!(output.contents(), "1\n2\n");
+    }
+
+    #[test]
+    fn equality_across_types() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"print nil == nil;
+print "a" == "a";
+print "a" != "b";
+print 1 == "1";
+print 1 != 2;
+print true == true;"#,
+        );
+
+        assert_eq!(output.contents(), "true\ntrue\ntrue\nfalse\ntrue\ntrue\n");
+    }
+
+    #[test]
+    fn methods_return_values() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Counter {
+    init(start) { this.count = start; }
+    next() { return this.count + 1; }
+}
+var counter = Counter(41);
+print counter.next();
+print counter.init(1) == counter;"#,
+        );
+
+        assert_eq!(output.contents(), "42\ntrue\n");
+    }
+
+    #[test]
+    fn to_string_native() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Point {
+    init(x) { this.x = x; }
+    toString() { return "Point(" + this.x + ")"; }
+}
+class Empty {}
+print to_string(1.5) == "1.5";
+print to_string(true) == "true";
+print to_string(nil) == "nil";
+print to_string(Point(1));
+print to_string(Empty());"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "true\ntrue\ntrue\nPoint(1)\ninstanceof(Empty)\n"
+        );
+    }
+
+    #[test]
+    fn assignment() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "var a = 1;\na = 2;\nprint a;\n{ var b = 1; b = a + b; a = 10; print b; }\nprint a;",
+        );
+
+        assert_eq!(output.contents(), "2\n3\n10\n");
+    }
+
+    #[test]
+    fn for_loop_has_its_own_scope() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "var i = \"outer\";\nfor (var i = 0; i < 3; i = i + 1) print i;\nprint i;",
+        );
+
+        assert_eq!(output.contents(), "0\n1\n2\nouter\n");
+    }
+
+    #[test]
+    fn postfix_increment_in_for_loop() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "var total = 0;\nfor (var i = 0; i < 4; i++) total = total + i;\nprint total;",
+        );
+
+        assert_eq!(output.contents(), "6\n");
+    }
+
+    #[test]
+    fn postfix_increment_yields_previous_value() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"var a = 5;
+var b = a++;
+print b;
+print a;
+print a--;
+print a;
+class Counter { init() { this.count = 0; } }
+var counter = Counter();
+counter.count++;
+print counter.count++;
+print counter.count;"#,
+        );
+
+        assert_eq!(output.contents(), "5\n6\n6\n5\n1\n2\n");
+    }
+
+    #[test]
+    fn prefix_and_postfix_increment_values() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"var i = 0;
+var a = i++;
+var b = ++i;
+print a;
+print b;
+print --i;
+var count = 0;
+for (var j = 0; j < 3; ++j) count++;
+print count;"#,
+        );
+
+        assert_eq!(output.contents(), "0\n2\n1\n3\n");
+    }
+
+    #[test]
+    fn local_functions_stay_local() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"fun outer() {
+    fun helper() { print "local"; }
+    helper();
+}
+var helper = "global";
+outer();
+print helper;"#,
+        );
+
+        assert_eq!(output.contents(), "local\nglobal\n");
+    }
+
+    #[test]
+    fn identical_expressions_in_one_line_resolve_separately() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "fun f() { var a = 1; fun g() { return a; } var a2 = a; return g; } print f()();",
+        );
+
+        assert_eq!(output.contents(), "1\n");
+    }
+
+    #[test]
+    fn block_expression_value_and_scope() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"fun compute() { return 21; }
+var t = "outer";
+var x = { var t = compute(); t * 2 };
+print x;
+print t;
+print { t = "changed"; { 1 } + 1 };
+print t;
+print { fun twice(n) { return n * 2; } twice(4) };"#,
+        );
+
+        assert_eq!(output.contents(), "42\nouter\n2\nchanged\n8\n");
+    }
+
+    #[test]
+    fn unary_plus() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(&interpreter, "print +5 == 5;\nvar x = -2;\nprint +x;");
+        assert_eq!(output.contents(), "true\n-2\n");
+
+        let error = try_run(&interpreter, "print +\"a\";").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::WrongUnaryOperands(
+                syntax::token::TokenType::Plus,
+                LoxValue::String(_)
+            )
+        ));
+    }
+
+    #[test]
+    fn match_statement() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"fun describe(value) {
+  match value {
+    1 => return "one";
+    -1 => return "minus one";
+    "s" => { return "a string"; },
+    nil => return "nothing";
+    _ => return "something else";
+  }
+}
+print describe(1);
+print describe(-1);
+print describe("s");
+print describe(nil);
+print describe(2);
+match 3 {
+  1 => print "one";
+  2 => print "two";
+}
+print "done";"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "one\nminus one\na string\nnothing\nsomething else\ndone\n"
+        );
+    }
+
+    #[test]
+    fn division_by_zero_errors_by_default() {
+        let (interpreter, _) = captured_interpreter();
+
+        let error = try_run(&interpreter, "print 1 / 0;").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::DivisionByZero
+        ));
+    }
+
+    #[test]
+    fn ieee_division_by_zero() {
+        let (mut interpreter, output) = captured_interpreter();
+        interpreter.set_ieee_division(true);
+
+        run(
+            &interpreter,
+            "print 1 / 0;\nprint -1 / 0;\nvar nan = 0 / 0;\nprint nan;\nprint nan == nan;",
+        );
+
+        assert_eq!(output.contents(), "inf\n-inf\nnan\nfalse\n");
+    }
+
+    #[test]
+    fn nan_comparisons() {
+        let (mut interpreter, output) = captured_interpreter();
+        interpreter.set_ieee_division(true);
+
+        run(
+            &interpreter,
+            r#"var nan = 0 / 0;
+print nan != nan;
+print nan < 1;
+print nan >= 1;
+print nan == 1;
+print is_nan(nan);
+print is_nan(1 / 0);
+print is_nan("nan");"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "true\nfalse\nfalse\nfalse\ntrue\nfalse\nfalse\n"
+        );
+    }
+
+    #[test]
+    fn string_length_property() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "print \"héllo\".length == 5;\nvar empty = \"\";\nprint empty.length;",
+        );
+        assert_eq!(output.contents(), "true\n0\n");
+
+        let error = try_run(&interpreter, "print \"abc\".size;").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::NotABuiltinProperty { field: ref f, .. } if f == "size"
+        ));
+        assert_eq!(
+            error.message(),
+            "Value of type string does not have a property called 'size'"
+        );
+    }
+
+    #[test]
+    fn string_repetition() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"print "ab" * 3;
+print 2 * "x";
+print "x" * 0 == "";
+print "ab" * 1.9;"#,
+        );
+
+        assert_eq!(output.contents(), "ababab\nxx\ntrue\nab\n");
+
+        let error = try_run(&interpreter, r#"print "ab" * -1;"#).unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::InvalidRepetitionCount(-1.0)
+        ));
+
+        let error = try_run(&interpreter, r#"print "ab" * 100000000000000000000;"#).unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::InvalidRepetitionCount(1e20)
+        ));
+        assert!(try_run(&interpreter, r#"print "ab" * 1000000000;"#).is_err());
+        assert!(try_run(&interpreter, r#"print "" * 100000000000000000000;"#).is_ok());
+    }
+
+    #[test]
+    fn exit_stops_the_script() {
+        let (interpreter, output) = captured_interpreter();
+
+        let code = try_run(
+            &interpreter,
+            "print 1;\nfun stop() { exit(3); }\nstop();\nprint 2;",
+        )
+        .unwrap();
+
+        assert_eq!(code, Some(ExitCode::from(3)));
+        assert_eq!(output.contents(), "1\n");
+    }
+
+    #[test]
+    fn exit_codes_must_fit_in_a_byte() {
+        let (interpreter, _) = captured_interpreter();
+
+        for code in ["-1", "256", "1.5"] {
+            let error = try_run(&interpreter, &format!("exit({code});")).unwrap_err();
+            assert!(matches!(
+                error.error_type,
+                InterpreterErrorType::Native(NativeError::InvalidExitCode(_))
+            ));
+        }
+
+        let code = try_run(&interpreter, "exit(255);").unwrap();
+        assert_eq!(code, Some(ExitCode::from(255)));
+    }
+
+    #[test]
+    fn logical_assignment_short_circuits() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"var calls = 0;
+fun fallback(value) { calls = calls + 1; return value; }
+var a = nil;
+a ||= fallback("default");
+print a;
+a ||= fallback("other");
+print a;
+var b = false;
+b &&= fallback(true);
+print b;
+b = 1;
+b &&= fallback(2);
+print b;
+print calls;"#,
+        );
+
+        assert_eq!(output.contents(), "default\ndefault\nfalse\n2\n2\n");
+    }
+
+    #[test]
+    fn logical_assignment_to_a_property_evaluates_the_object_once() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Box {}
+var box = Box();
+box.value = nil;
+var lookups = 0;
+fun get() { lookups = lookups + 1; return box; }
+print get().value ||= "set";
+print get().value ||= "ignored";
+print get().value &&= "replaced";
+print box.value;
+print lookups;"#,
+        );
+
+        assert_eq!(output.contents(), "set\nset\nreplaced\nreplaced\n3\n");
+    }
+
+    #[test]
+    fn undefined_variable_vs_access_before_declaration() {
+        let (interpreter, _) = captured_interpreter();
+
+        let error = try_run(&interpreter, "var count = 1;\nprint cuont;").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::UndefinedVariable(ref name) if name == "cuont"
+        ));
+
+        let error = try_run(
+            &interpreter,
+            "fun show() { print later; }\nshow();\nvar later = 2;",
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::AccessBeforeInitialization {
+                ref name,
+                declaration_line: Some(3),
+            } if name == "later"
+        ));
+    }
+
+    #[test]
+    fn comma_operator() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"var a = 0;
+var b = (a = 1, a + 1, a + 2);
+print b;
+fun pair(x, y) { return x + y; }
+print pair(1, 2);
+print pair((a = 5, a), 1);"#,
+        );
+
+        assert_eq!(output.contents(), "3\n3\n6\n");
+    }
+
+    #[test]
+    fn forget_undefined_globals() {
+        let (interpreter, _) = captured_interpreter();
+
+        assert!(try_run(&interpreter, "var a = 1;\nvar b = undefined;").is_err());
+        interpreter.forget_undefined_globals();
+
+        assert_eq!(interpreter.global_declaration("a"), Some(Some(1)));
+        assert_eq!(interpreter.global_declaration("b"), None);
+    }
+
+    #[test]
+    fn reset_globals_keeps_natives() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(&interpreter, "var kept = 1;\nvar dropped = 2;");
+        assert_eq!(interpreter.global_names(), ["dropped", "kept"]);
+        assert!(interpreter.undefine_global("dropped"));
+        assert!(!interpreter.undefine_global("dropped"));
+        run(&interpreter, "print kept;");
+
+        interpreter.reset_globals();
+
+        let error = try_run(&interpreter, "print kept;").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::UndefinedVariable(_)
+        ));
+        run(&interpreter, "print mod(7, 3);");
+        assert_eq!(output.contents(), "1\n1\n");
+        assert!(interpreter.global_names().is_empty());
+    }
+
+    #[test]
+    fn bound_methods_keep_this() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Greeter {
+  init(name) { this.name = name; }
+  greet() { return "hi " + this.name; }
+}
+var greet;
+{
+  var greeter = Greeter("ana");
+  greet = greeter.greet;
+}
+fun call(f) {
+  var name = "shadowed";
+  return f();
+}
+print call(greet);
+print greet;
+print Greeter("bo").greet == greet;
+class Other {}
+var other = Other();
+other.greet = greet;
+print other.greet();"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "hi ana\n<bound method Greeter.greet>\nfalse\nhi ana\n"
+        );
+    }
+
+    #[test]
+    fn nested_property_assignment() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "class Node {}\nvar a = Node();\na.b = Node();\na.b.c = 3;\nprint a.b.c;\na.b.c = a.b.c + 1;\nprint a.b.c;",
+        );
+
+        assert_eq!(output.contents(), "3\n4\n");
+    }
+
+    #[test]
+    fn frozen_instances_reject_writes() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "class Point {}\nvar p = Point();\np.x = 1;\nfreeze(p);\nprint p.x;",
+        );
+        assert_eq!(output.contents(), "1\n");
+
+        let error = try_run(&interpreter, "p.x = 2;").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::FrozenInstance { ref field, .. } if field == "x"
+        ));
+        let error = try_run(&interpreter, "p.x++;").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::FrozenInstance { .. }
+        ));
+
+        run(&interpreter, "print p.x;");
+        assert_eq!(output.contents(), "1\n1\n");
+    }
+
+    #[test]
+    fn for_loop_closures_capture_each_iteration() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"var first;
+var second;
+for (var i = 0; i < 2; i = i + 1) {
+  fun show() { print i; }
+  if (i == 0) first = show; else second = show;
+}
+first();
+second();
+var total = 0;
+for (var j = 0; j < 5; j = j + 1) {
+  if (j == 2) continue;
+  total = total + j;
+}
+print total;"#,
+        );
+
+        assert_eq!(output.contents(), "0\n1\n8\n");
+    }
+
+    #[test]
+    fn continue_inside_nested_while_blocks() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"var i = 0;
+var sum = 0;
+while (i < 6) {
+  i = i + 1;
+  if (i == 2 or i == 4) {
+    continue;
+    print "unreachable";
+  }
+  sum = sum + i;
+}
+print sum;
+print i;"#,
+        );
+
+        assert_eq!(output.contents(), "15\n6\n");
+    }
+
+    #[test]
+    fn format_replaces_placeholders() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"print format("{1} before {0}", "a", 2);
+print format("{0}{0}{0}", "ab");
+print format("{{{0}}} and }}", nil);
+print format("no placeholders");"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "2 before a\nababab\n{nil} and }\nno placeholders\n"
+        );
+
+        let error = try_run(&interpreter, r#"format("{0} {1}", 1);"#).unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::Native(NativeError::FormatArgument(1))
+        ));
+        let error = try_run(&interpreter, r#"format("{18446744073709551615}", 1);"#).unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::Native(NativeError::FormatArgument(usize::MAX))
+        ));
+        let error = try_run(&interpreter, r#"format("{x}", 1);"#).unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::Native(NativeError::FormatTemplate(_))
+        ));
+        let error = try_run(&interpreter, "format();").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::WrongArity { .. }
+        ));
+    }
+
+    #[test]
+    fn undefined_sibling_method_suggests_this() {
+        let (interpreter, _) = captured_interpreter();
+
+        let error = try_run(
+            &interpreter,
+            r#"class Greeter {
+  helper() { return "hi"; }
+  greet() { return helper(); }
+}
+Greeter().greet();"#,
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::MethodWithoutThis(ref name) if name == "helper"
+        ));
+        assert!(error.to_string().contains("Did you mean this.helper()?"));
+
+        let error = try_run(&interpreter, "helper();").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::UndefinedVariable(_)
+        ));
+    }
+
+    #[test]
+    fn getters_reading_other_properties() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Rectangle {
+  init(width, height) {
+    this.width = width;
+    this.height = height;
+  }
+  area() { return this.width * this.height; }
+  describe() { return "area " + this.area(); }
+  cache() { this.cached = this.area() + this.area(); }
+}
+{
+  var rectangle = Rectangle(2, 3);
+  var description = rectangle.describe();
+  print description;
+  rectangle.cache();
+  print rectangle.cached;
+  rectangle.width = rectangle.area();
+  print rectangle.describe();
+}"#,
+        );
+
+        assert_eq!(output.contents(), "area 6\n12\narea 18\n");
+    }
+
+    #[test]
+    fn catch_thrown_values() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"fun check(n) {
+  if (n < 0) throw "negative";
+  return n;
+}
+try {
+  print check(1);
+  print check(-1);
+  print "unreachable";
+} catch (e) {
+  print "caught " + e;
+}
+for (var i = 0; i < 3; i = i + 1) {
+  try {
+    if (i == 1) throw i;
+  } catch (e) {
+    print e;
+    break;
+  }
+}"#,
+        );
+
+        assert_eq!(output.contents(), "1\ncaught negative\n1\n");
+    }
+
+    #[test]
+    fn uncaught_throw_is_a_runtime_error() {
+        let (interpreter, output) = captured_interpreter();
+
+        let error = try_run(&interpreter, "print 1;\nthrow 42;\nprint 2;").unwrap_err();
+
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::Thrown(LoxValue::Number(n)) if n == 42.0
+        ));
+        assert_eq!(error.message(), "Uncaught exception: 42");
+        assert_eq!(error.token.line(), 2);
+        assert_eq!(output.contents(), "1\n");
+    }
+
+    #[test]
+    fn finally_always_runs() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"try {
+  print "body";
+} catch (e) {
+  print "unreachable";
+} finally {
+  print "finally after body";
+}
+try {
+  throw "oops";
+} catch (e) {
+  print "caught " + e;
+} finally {
+  print "finally after catch";
+}
+fun early() {
+  try {
+    return "returned";
+  } catch (e) {
+  } finally {
+    print "finally before return";
+  }
+  return "unreachable";
+}
+print early();
+while (true) {
+  try { break; } catch (e) {} finally { print "finally before break"; }
+}"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "body\nfinally after body\ncaught oops\nfinally after catch\n\
+             finally before return\nreturned\nfinally before break\n"
+        );
+    }
+
+    #[test]
+    fn loop_control_through_finally() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"for (var i = 0; i < 4; i = i + 1) {
+  try {
+    if (i == 1) continue;
+    if (i == 3) break;
+    print "body " + i;
+  } catch (e) {
+  } finally {
+    print "finally " + i;
+  }
+}
+var j = 0;
+while (j < 2) {
+  j = j + 1;
+  try {
+    throw j;
+  } catch (e) {
+    continue;
+  } finally {
+    print "caught " + j;
+  }
+  print "unreachable";
+}"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "body 0\nfinally 0\nfinally 1\nbody 2\nfinally 2\nfinally 3\n\
+             caught 1\ncaught 2\n"
+        );
+    }
+
+    #[test]
+    fn finally_keeps_pending_throw() {
+        let (interpreter, output) = captured_interpreter();
+
+        let error = try_run(
+            &interpreter,
+            r#"try {
+  print "body";
+} catch (e) {
+  throw "rethrown " + e;
+} finally {
+  print "finally";
+}
+try {
+  throw 1;
+} catch (e) {
+  throw "again";
+} finally {
+  print "finally";
+}"#,
+        )
+        .unwrap_err();
+
+        assert_eq!(error.message(), "Uncaught exception: again");
+        assert_eq!(output.contents(), "body\nfinally\nfinally\n");
+    }
+
+    #[test]
+    fn round_to() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "print round_to(3.14159, 2) == 3.14;\nprint round_to(3.14159, 0);",
+        );
+
+        assert_eq!(output.contents(), "true\n3\n");
+
+        let error = try_run(&interpreter, "round_to(3.14159, -2);").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::Native(NativeError::InvalidDigits {
+                function: "round_to",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn arrow_functions() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"var answer = () => 42;
+var double = (x) => x * 2;
+var add = (a, b) => a + b;
+print answer();
+print double(21);
+print add(40, 2);
+fun adder(n) { return (x) => x + n; }
+print adder(2)(40);
+print add;"#,
+        );
+
+        assert_eq!(output.contents(), "42\n42\n42\n42\n<fun lambda(a, b)>\n");
+    }
+
+    #[test]
+    fn chr_ord_round_trip() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "print ord(\"A\") == 65;\nprint chr(65) == \"A\";\nprint chr(ord(\"ñ\"));",
+        );
+
+        assert_eq!(output.contents(), "true\ntrue\nñ\n");
+    }
+
+    #[test]
+    fn print_multiple_values() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(&interpreter, "print 1;\nprint \"a\", 2 + 3, nil;");
+
+        assert_eq!(output.contents(), "1\na 5 nil\n");
+    }
+
+    #[test]
+    fn input_prompts_and_reads_a_line() {
+        let (mut interpreter, output) = captured_interpreter();
+        interpreter.set_input(Cursor::new("Ferris\r\n42\n"));
+
+        run(
+            &interpreter,
+            "var name = input(\"Name: \");\nprint \"Hello, \" + name;\nprint read_line();",
+        );
+
+        assert_eq!(output.contents(), "Name: Hello, Ferris\n42\n");
+    }
+
+    #[test]
+    fn inherited_methods_bind_the_calling_instance() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Shape {
+  init(name) { this.name = name; }
+  describe() { return this.name + " with " + this.sides() + " sides"; }
+  sides() { return 0; }
+  namer() {
+    fun name() { return this.name; }
+    return name;
+  }
+}
+class Square < Shape {
+  sides() { return 4; }
+}
+var square = Square("square");
+print square.describe();
+var name = square.namer();
+print Shape("circle").describe();
+print name();"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "square with 4 sides\ncircle with 0 sides\nsquare\n"
+        );
+    }
+
+    #[test]
+    fn static_fields_are_shared_by_instances() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Counter {
+  static var count = 0;
+  static var label;
+  increment() { Counter.count = Counter.count + 1; }
+}
+class Sub < Counter {}
+var a = Counter();
+var b = Sub();
+a.increment();
+b.increment();
+Sub.count++;
+print Counter.count;
+print Sub.count;
+print Counter.label;"#,
+        );
+
+        assert_eq!(output.contents(), "3\n3\nnil\n");
+    }
+
+    #[test]
+    fn undeclared_static_field() {
+        let (interpreter, _) = captured_interpreter();
+
+        let error = try_run(&interpreter, "class Counter {}\nCounter.count = 1;").unwrap_err();
+
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::NotAStaticField { ref field, .. } if field == "count"
+        ));
+    }
+
+    #[test]
+    fn fields_are_enumerated_in_order() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "class Point {}\nvar p = Point();\np.y = 2;\np.x = 1;\np.z = 3;\nprint fields(p);",
+        );
+
+        assert_eq!(output.contents(), "y, x, z\n");
+    }
+
+    #[test]
+    fn deep_equals_compares_fields() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Point { init(x, y) { this.x = x; this.y = y; } }
+class Other { init(x, y) { this.x = x; this.y = y; } }
+var a = Point(1, Point(2, "three"));
+var b = Point(1, Point(2, "three"));
+print a == b;
+print deep_equals(a, b);
+print deep_equals(a, Point(1, Point(2, "four")));
+print deep_equals(Point(1, 2), Other(1, 2));
+b.z = 3;
+print deep_equals(a, b);
+print deep_equals("text", "text");"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "false\ntrue\nfalse\nfalse\nfalse\ntrue\n"
+        );
+    }
+
+    #[test]
+    fn deep_equals_handles_cycles() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Node {}
+var a = Node();
+var b = Node();
+a.next = a;
+b.next = b;
+print deep_equals(a, b);"#,
+        );
+
+        assert_eq!(output.contents(), "true\n");
+    }
+
+    #[test]
+    fn native_reports_bad_argument() {
+        let (interpreter, _) = captured_interpreter();
+
+        let error = try_run(&interpreter, "var x = 1;\nprint round_to(x, \"2\");").unwrap_err();
+
+        assert_eq!(error.token.line(), 2);
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::Native(NativeError::BadArgument { index: 2, .. })
+        ));
+        assert_eq!(
+            error.message(),
+            "Native Error - Argument 2 to round_to expected number, got string"
+        );
+    }
+
+    #[test]
+    fn print_callable_signatures() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "fun greet(name, greeting) {}\nprint greet;\nprint clock;\nprint round_to;\nprint format;",
+        );
+
+        assert_eq!(
+            output.contents(),
+            "<fun greet(name, greeting)>\n<native fun/0>\n<native fun/2>\n<native fun/1+>\n"
+        );
+    }
+
+    #[test]
+    fn loop_else_runs_without_break() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"for (var i = 0; i < 3; i = i + 1) {} else { print "for done"; }
+for (var i = 0; i < 3; i = i + 1) {
+  if (i == 1) break;
+} else { print "for skipped"; }
+var n = 0;
+while (n < 3) { n = n + 1; continue; } else print "while done";
+while (true) { break; } else { print "while skipped"; }
+while (false) {} else print "never entered";"#,
+        );
+
+        assert_eq!(output.contents(), "for done\nwhile done\nnever entered\n");
+    }
+
+    #[test]
+    fn else_after_a_loop_in_an_if_belongs_to_the_if() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"var x = false;
+if (x) while (false) {} else print "if else";
+if (!x) { while (false) {} else print "loop else"; }"#,
+        );
+
+        assert_eq!(output.contents(), "if else\nloop else\n");
+    }
+
+    #[test]
+    fn break_in_nested_loop_else() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"for (var i = 0; i < 3; i = i + 1) {
+  for (var j = 0; j < 1; j = j + 1) {} else {
+    if (i == 1) break;
+  }
+  print i;
+}"#,
+        );
+
+        assert_eq!(output.contents(), "0\n");
+    }
+
+    #[test]
+    fn assert_eq_passes_on_equal_values() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Point { init(x, y) { this.x = x; this.y = y; } }
+assert_eq(4, 2 + 2);
+assert_eq("ab", "a" + "b");
+assert_eq(Point(1, 2), Point(1, 2));
+print "passed";"#,
+        );
+
+        assert_eq!(output.contents(), "passed\n");
+    }
+
+    #[test]
+    fn assert_eq_describes_mismatches() {
+        let (interpreter, output) = captured_interpreter();
+
+        let error =
+            try_run(&interpreter, "assert_eq(4, 2 + 3);\nprint \"unreachable\";").unwrap_err();
+
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::Native(NativeError::AssertionFailed { .. })
+        ));
+        assert_eq!(
+            error.message(),
+            "Native Error - Assertion failed: expected 4, got 5"
+        );
+        assert_eq!(output.contents(), "");
+    }
+
+    #[test]
+    fn assert_throws_passes_on_errors() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"fun divide() {
+  return 1 / 0;
+}
+assert_throws(divide);
+assert_throws(() => nil.field);
+print "passed";"#,
+        );
+
+        assert_eq!(output.contents(), "passed\n");
+    }
+
+    #[test]
+    fn assert_throws_fails_without_errors() {
+        let (interpreter, output) = captured_interpreter();
+
+        let error = try_run(
+            &interpreter,
+            "assert_throws(() => 1);\nprint \"unreachable\";",
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::Native(NativeError::NothingThrown)
+        ));
+        assert_eq!(
+            error.message(),
+            "Native Error - Assertion failed: expected an error, but none was raised"
+        );
+        assert_eq!(output.contents(), "");
+    }
+
+    #[test]
+    fn assert_throws_rejects_functions_with_parameters() {
+        let (interpreter, _) = captured_interpreter();
+
+        let error = try_run(&interpreter, "assert_throws((x) => x);").unwrap_err();
+
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::Native(NativeError::BadArgument {
+                function: "assert_throws",
+                index: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn functions_are_hoisted() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"print isEven(10);
+fun isEven(n) {
+  if (n == 0) return true;
+  return isOdd(n - 1);
+}
+fun isOdd(n) {
+  if (n == 0) return false;
+  return isEven(n - 1);
+}
+{
+  var before = half(8);
+  print before;
+  fun half(n) { return n / 2; }
+  print isOdd(7);
+}"#,
+        );
+
+        assert_eq!(output.contents(), "true\n4\ntrue\n");
+    }
+
+    #[test]
+    fn classes_are_declared_in_order() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Shape { name() { return "outer"; } }
+{
+  print Shape().name();
+  class Shape { name() { return "inner"; } }
+  print Shape().name();
+}"#,
+        );
+
+        assert_eq!(output.contents(), "outer\ninner\n");
+    }
+
+    #[test]
+    fn hoisting_keeps_variable_order() {
+        let (interpreter, output) = captured_interpreter();
+
+        let error = try_run(
+            &interpreter,
+            "{\n  fun read() { return later; }\n  print read();\n  var later = 1;\n}",
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::UndefinedVariable(ref name) if name == "later"
+        ));
+        assert_eq!(output.contents(), "");
+    }
+
+    #[test]
+    fn repr_differs_from_to_string() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"print to_string("a\nb");
+print repr("a\nb");
+print repr("say \"hi\" \\ bye");
+print repr(1.5);
+print repr(nil);
+class Point { init(x, y) { this.x = x; this.y = y; } }
+var p = Point(1, "a");
+print repr(p);
+p.self = p;
+print repr(p);"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            r#"a
+b
+"a\nb"
+"say \"hi\" \\ bye"
+1.5
+nil
+Point(x: 1, y: "a")
+Point(x: 1, y: "a", self: ...)
+"#
+        );
+    }
+
+    #[test]
+    fn bound_methods_show_their_class() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Greeter {
+  greet(name) { return "hi " + name; }
+}
+class Loud < Greeter {}
+var greeter = Greeter();
+print greeter.greet;
+print Loud().greet;
+fun greet(name) { return name; }
+print greet;
+print greeter.greet("ana");"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "<bound method Greeter.greet>\n<bound method Loud.greet>\n<fun greet(name)>\nhi ana\n"
+        );
+
+        let error = try_run(&interpreter, "to_hex(greeter.greet);").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::Native(NativeError::BadArgument {
+                got: "bound method",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn calling_a_data_property_names_it() {
+        let (interpreter, _) = captured_interpreter();
+
+        let error = try_run(
+            &interpreter,
+            "class Foo {}\nvar foo = Foo();\nfoo.data = 1;\nfoo.data();",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.message(),
+            "Property 'data' of Foo is a number, not callable"
+        );
+
+        let error = try_run(&interpreter, "foo.data = Foo();\nfoo.data();").unwrap_err();
+        assert_eq!(
+            error.message(),
+            "Property 'data' of Foo is an instance, not callable"
+        );
+
+        let error = try_run(&interpreter, "\"abc\".length();").unwrap_err();
+        assert_eq!(
+            error.message(),
+            "Property 'length' of string is a number, not callable"
+        );
+    }
+
+    #[test]
+    fn prelude_functions_are_callable() {
+        let (interpreter, output) = captured_interpreter();
+        interpreter.load_prelude(PRELUDE).unwrap();
+
+        run(
+            &interpreter,
+            r#"var double = (x) => x * 2;
+fun show(i) {
+    print i;
+}
+print compose(double, identity)(4);
+times(3, show);"#,
+        );
+
+        assert_eq!(output.contents(), "8\n0\n1\n2\n");
+    }
+
+    #[test]
+    fn prelude_declarations_have_no_line() {
+        let interpreter = Interpreter::new();
+        interpreter.declare_global("before", Some(1));
+        interpreter.load_prelude(PRELUDE).unwrap();
+
+        assert_eq!(interpreter.global_declaration("identity"), Some(None));
+        assert_eq!(interpreter.global_declaration("before"), Some(Some(1)));
+    }
+
+    #[test]
+    fn prelude_errors_are_reported() {
+        let interpreter = Interpreter::new();
+        assert!(matches!(
+            interpreter.load_prelude("fun broken( {"),
+            Err(PreludeError::Parse(_))
+        ));
+        assert!(matches!(
+            interpreter.load_prelude("undefined();"),
+            Err(PreludeError::Runtime(_))
+        ));
+    }
+
+    #[test]
+    fn eval_returns_the_value_of_an_expression() {
+        let interpreter = Interpreter::new();
+
+        assert_eq!(interpreter.eval("1 + 2").unwrap(), LoxValue::Number(3.0));
+        assert_eq!(
+            interpreter.eval("((x) => x * 2)(21)").unwrap(),
+            LoxValue::Number(42.0)
+        );
+    }
+
+    #[test]
+    fn eval_rejects_statements_and_trailing_tokens() {
+        let interpreter = Interpreter::new();
+
+        for source in ["var x;", "1 + 2;", "1 2", ""] {
+            let error = interpreter.eval(source).unwrap_err();
+            assert!(
+                matches!(error.error_type, InterpreterErrorType::InvalidExpression(_)),
+                "{source} gave {error}"
+            );
+        }
+    }
+
+    #[test]
+    fn number_formatting_natives() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"print to_fixed(3.14159, 2) == "3.14";
+print to_fixed(2.5, 0);
+print to_fixed(-1, 3);
+print to_exponential(12345, 2);
+print to_exponential(0.00015, 1);"#,
+        );
+
+        assert_eq!(output.contents(), "true\n2\n-1.000\n1.23e4\n1.5e-4\n");
+
+        for source in ["to_fixed(1, 0.5);", "to_exponential(1, 101);"] {
+            let error = try_run(&interpreter, source).unwrap_err();
+            assert!(matches!(
+                error.error_type,
+                InterpreterErrorType::Native(NativeError::InvalidDigits { .. })
+            ));
+        }
+
+        let error = try_run(&interpreter, "to_fixed(\"1\", 2);").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::Native(NativeError::BadArgument { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn bytes_values() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"var bytes = from_hex("48656c6c6f");
+print bytes;
+print bytes.length;
+print byte_at(bytes, 0);
+print bytes[4];
+print to_hex(bytes);
+print bytes == from_hex("48656C6C6F");
+print from_hex("000102030405060708090a0b0c0d0e0f10");"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "<bytes(5) 48 65 6c 6c 6f>\n5\n72\n111\n48656c6c6f\ntrue\n\
+             <bytes(17) 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f ...>\n"
+        );
+
+        let error = try_run(&interpreter, "print bytes[5];").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::InvalidIndex { length: 5, .. }
+        ));
+        let error = try_run(&interpreter, "print bytes[\"0\"];").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::InvalidIndex { .. }
+        ));
+        let error = try_run(&interpreter, "print \"abc\"[0];").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::NotIndexable("string")
+        ));
+    }
+
+    #[test]
+    fn infinity_and_nan_values() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"print inf();
+print -inf();
+print nan();
+print inf() + 1 == inf();
+print inf() - inf();
+print 1 / inf();
+print -inf() < -1000000;
+print is_nan(nan() * 0);
+print "value: " + nan();"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "inf\n-inf\nnan\ntrue\nnan\n0\ntrue\ntrue\nvalue: nan\n"
+        );
+    }
+
+    #[test]
+    fn with_closes_the_resource_once() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class File {
+    init(name) {
+        this.name = name;
+    }
+    close() {
+        print "closed " + this.name;
+    }
+}
+fun first() {
+    with (File("a")) {
+        return "returned";
+        print "unreachable";
+    }
+}
+print first();
+for (var i = 0; i < 3; i++) {
+    with (File("b")) {
+        break;
+    }
+}
+try {
+    with (File("c")) {
+        throw "thrown";
+    }
+} catch (e) {
+    print e;
+}
+with (1) {
+    print "not a resource";
+}"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "closed a\nreturned\nclosed b\nclosed c\nthrown\nnot a resource\n"
+        );
+    }
+
+    #[test]
+    fn number_literals_print_as_written() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            "print 5;\nprint 5.0;\nprint 2.50;\nprint 10.0 / 2.0;\nvar x = 5.0;\nprint x;",
+        );
+
+        assert_eq!(output.contents(), "5\n5.0\n2.5\n5\n5\n");
+    }
+
+    #[test]
+    fn eprint_writes_to_the_error_output() {
+        let (mut interpreter, output) = captured_interpreter();
+        let error_output = SharedBuffer::default();
+        interpreter.set_error_output(error_output.clone());
+
+        run(
+            &interpreter,
+            "print 1;\neprint(\"warning: \");\neprintln(2);\nprint 3;",
+        );
+
+        assert_eq!(output.contents(), "1\n3\n");
+        assert_eq!(error_output.contents(), "warning: 2\n");
+    }
+
+    #[test]
+    fn debug_env_reports_the_depth() {
+        let (mut interpreter, _) = captured_interpreter();
+        let error_output = SharedBuffer::default();
+        interpreter.set_error_output(error_output.clone());
+
+        run(
+            &interpreter,
+            "debug_env();\nfun f(a) {\n  var b;\n  {\n    debug_env();\n  }\n}\nf(1);",
+        );
+
+        let globals = interpreter.globals.borrow().local_count();
+        assert_eq!(
+            error_output.contents(),
+            format!(
+                "[line 1] Environment depth: 0, with {globals} variables\n\
+                 [line 5] Environment depth: 2, with 0 variables\n"
+            )
+        );
+    }
+
+    #[test]
+    fn subclass_initializers_call_super_init() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Shape {
+    init(name) {
+        this.name = name;
+        this.sides = 0;
+    }
+    describe() {
+        return this.name + " with " + to_string(this.sides) + " sides";
+    }
+}
+class Polygon < Shape {
+    init(name, sides) {
+        super.init(name);
+        this.sides = sides;
+    }
+}
+class Square < Polygon {
+    init() {
+        super.init("square", 4);
+    }
+    describe() {
+        return "a " + super.describe();
+    }
+}
+var square = Square();
+print square.name;
+print square.sides;
+print square.describe();
+print Polygon("triangle", 3).describe();"#,
+        );
+
+        assert_eq!(
+            output.contents(),
+            "square\n4\na square with 4 sides\ntriangle with 3 sides\n"
+        );
+    }
+
+    #[test]
+    fn static_methods_are_called_on_the_class() {
+        let (interpreter, output) = captured_interpreter();
+
+        run(
+            &interpreter,
+            r#"class Point {
+    static var origins = 0;
+    init(x, y) {
+        this.x = x;
+        this.y = y;
+    }
+    static origin() {
+        Point.origins++;
+        return Point(0, 0);
+    }
+}
+var origin = Point.origin();
+print origin.x, origin.y;
+print Point.origins;"#,
+        );
+
+        assert_eq!(output.contents(), "0 0\n1\n");
+    }
+
+    #[test]
+    fn superclass_must_be_a_class() {
+        let (interpreter, _) = captured_interpreter();
+
+        let error =
+            try_run(&interpreter, "var NotAClass = 1;\nclass A < NotAClass {}").unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            InterpreterErrorType::InvalidSuperClass
+        ));
     }
 }
@@ -1,7 +1,7 @@
 use crate::interpreter::environment::Environment;
-use crate::interpreter::{LoxValue, NativeResult};
+use crate::interpreter::{Interpreter, InterpreterResult, LoxValue, NativeResult};
 use std::cell::RefCell;
-use std::fmt::{Debug, Formatter};
+use std::fmt::{Debug, Display, Formatter};
 use std::rc::Rc;
 use syntax::statement::Block;
 use syntax::token::Token;
@@ -10,6 +10,37 @@ use super::value::Instance;
 
 pub type NativeFunc = fn(args: &[LoxValue]) -> NativeResult<LoxValue>;
 
+/// A native function that needs access to the interpreter, for example to call back into Lox
+/// code. It receives the token of the call site to report errors.
+pub type NativeMethod =
+    fn(interpreter: &Interpreter, args: &[LoxValue], token: &Token) -> InterpreterResult<LoxValue>;
+
+/// The number of arguments a callable accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arity {
+    Fixed(usize),
+    /// Variadic natives take any number of arguments from the given minimum onwards.
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(self, count: usize) -> bool {
+        match self {
+            Arity::Fixed(arity) => count == arity,
+            Arity::AtLeast(minimum) => count >= minimum,
+        }
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arity::Fixed(arity) => write!(f, "{arity}"),
+            Arity::AtLeast(minimum) => write!(f, "at least {minimum}"),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct LoxFunction {
     pub closure: Rc<RefCell<Environment>>,
@@ -23,36 +54,79 @@ pub struct LoxFunction {
 pub enum Callable {
     Native {
         func: NativeFunc,
-        arity: usize,
+        arity: Arity,
+    },
+    NativeMethod {
+        func: NativeMethod,
+        arity: Arity,
     },
     LoxFunction(LoxFunction),
+    /// A method read from an instance. It is only bound to `receiver` when called, so it can
+    /// still tell which method it is and whose.
+    Bound {
+        method: Rc<LoxFunction>,
+        receiver: Rc<Instance>,
+    },
     Constructor {
         class: Rc<super::value::Class>,
-        arity: usize,
+        arity: Arity,
     },
 }
 
 impl Callable {
-    pub fn arity(&self) -> usize {
+    pub fn is_native(&self) -> bool {
+        matches!(self, Self::Native { .. } | Self::NativeMethod { .. })
+    }
+
+    pub fn arity(&self) -> Arity {
         match self {
-            Self::Native { arity, .. } => *arity,
-            Self::LoxFunction(function) => function.params.len(),
-            Self::Constructor { arity, .. } => *arity,
+            Self::Native { arity, .. }
+            | Self::NativeMethod { arity, .. }
+            | Self::Constructor { arity, .. } => *arity,
+            Self::LoxFunction(function) => Arity::Fixed(function.params.len()),
+            Self::Bound { method, .. } => Arity::Fixed(method.params.len()),
         }
     }
 }
 
+/// Functions show their parameters, as in `<fun greet(name, greeting)>`, and bound methods their
+/// class, as in `<bound method Greeter.greet>`. Natives have no parameter names, so they show
+/// how many arguments they take instead, as in `<native fun/2>` or `<native fun/1+>` for
+/// variadic ones.
 impl Debug for Callable {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Native { func: _, arity: _ } => f.write_str("<native fun>"),
-            Self::LoxFunction(function) => write!(f, "<fun {}>", function.name),
+            Self::Native { arity, .. } | Self::NativeMethod { arity, .. } => match arity {
+                Arity::Fixed(arity) => write!(f, "<native fun/{arity}>"),
+                Arity::AtLeast(minimum) => write!(f, "<native fun/{minimum}+>"),
+            },
+            Self::LoxFunction(function) => {
+                let params: Vec<&str> = function.params.iter().map(Token::lexeme).collect();
+                write!(f, "<fun {}({})>", function.name, params.join(", "))
+            }
+            Self::Bound { method, receiver } => {
+                write!(
+                    f,
+                    "<bound method {}.{}>",
+                    receiver.class_name(),
+                    method.name
+                )
+            }
             Self::Constructor { class, .. } => write!(f, "<constructor {class}>"),
         }
     }
 }
 
 impl LoxFunction {
+    /// Returns the instance the method was bound to with [`LoxFunction::bind`], if any. Bound
+    /// methods are the only functions whose closure defines `this`.
+    pub fn bound_instance(&self) -> Option<Rc<Instance>> {
+        match self.closure.borrow().get_at("this", 0) {
+            Some(LoxValue::Instance(instance)) => Some(instance),
+            _ => None,
+        }
+    }
+
     pub fn bind(&self, instance: Rc<Instance>) -> LoxFunction {
         let mut environment = Environment::new_enclosed(self.closure.clone());
         environment.define(String::from("this"), LoxValue::Instance(instance.clone()));
@@ -60,7 +134,7 @@ impl LoxFunction {
         LoxFunction {
             closure: Rc::new(RefCell::new(environment)),
             name: self.name.to_string(),
-            is_initializer: true,
+            is_initializer: self.is_initializer,
             params: self.params.clone(),
             block: self.block.clone(),
         }
@@ -1,15 +1,20 @@
 use crate::interpreter::value::LoxValue;
 use std::cell::RefCell;
-use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use std::rc::Rc;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Environment {
     values: HashMap<String, LoxValue>,
     enclosing: Option<Rc<RefCell<Self>>>,
 }
 
+/// The bindings of a single environment at some point, see [`Environment::snapshot`].
+pub struct EnvSnapshot {
+    values: HashMap<String, LoxValue>,
+}
+
 impl Environment {
     pub fn new() -> Self {
         Self {
@@ -29,6 +34,48 @@ impl Environment {
         self.values.insert(name, value);
     }
 
+    /// Removes `name` from this environment, without looking at the enclosing ones. Returns
+    /// whether the variable was defined.
+    pub fn undefine(&mut self, name: &str) -> bool {
+        self.values.remove(name).is_some()
+    }
+
+    /// Saves the bindings of this environment, without the enclosing ones, so they can be rolled
+    /// back with [`Environment::restore`]. Values are shared, so changes to the fields of an
+    /// instance are not undone.
+    pub fn snapshot(&self) -> EnvSnapshot {
+        EnvSnapshot {
+            values: self.values.clone(),
+        }
+    }
+
+    /// Brings back the bindings saved by [`Environment::snapshot`], dropping every variable
+    /// defined and undoing every assignment made since.
+    pub fn restore(&mut self, snapshot: EnvSnapshot) {
+        self.values = snapshot.values;
+    }
+
+    /// Iterates over the variables defined in this environment, without the enclosing ones.
+    pub fn variables(&self) -> impl Iterator<Item = (&String, &LoxValue)> {
+        self.values.iter()
+    }
+
+    /// Returns how many environments enclose this one, which is 0 for the globals.
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut environment = self.enclosing.clone();
+        while let Some(enclosing) = environment {
+            depth += 1;
+            environment = enclosing.borrow().enclosing.clone();
+        }
+        depth
+    }
+
+    /// Returns how many variables are defined in this environment, without the enclosing ones.
+    pub fn local_count(&self) -> usize {
+        self.values.len()
+    }
+
     pub fn assign_at(&mut self, name: &str, value: LoxValue, distance: usize) -> bool {
         match self.ancestor(distance) {
             Some(ancestor) => {
@@ -64,7 +111,13 @@ impl Environment {
         }
     }
 
+    /// Returns the environment `distance` levels above this one. A distance of 0 refers to the
+    /// environment itself, which is represented as `None`.
     fn ancestor(&self, distance: usize) -> Option<Rc<RefCell<Environment>>> {
+        if distance == 0 {
+            return None;
+        }
+
         let mut environment: Option<Rc<RefCell<Environment>>> = self.enclosing.clone();
 
         for _ in 1..distance {
@@ -87,3 +140,41 @@ impl Environment {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Environment;
+    use crate::interpreter::LoxValue;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn restore_drops_later_changes() {
+        let mut environment = Environment::new();
+        environment.define(String::from("kept"), LoxValue::Number(1.0));
+
+        let snapshot = environment.snapshot();
+        environment.define(String::from("added"), LoxValue::Nil);
+        assert!(environment.assign_at("kept", LoxValue::Number(2.0), 0));
+        assert_eq!(environment.get("added"), Some(LoxValue::Nil));
+
+        environment.restore(snapshot);
+        assert_eq!(environment.get("added"), None);
+        assert_eq!(environment.get("kept"), Some(LoxValue::Number(1.0)));
+    }
+
+    #[test]
+    fn depth_counts_enclosing_environments() {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        let outer = Rc::new(RefCell::new(Environment::new_enclosed(globals.clone())));
+        let mut inner = Environment::new_enclosed(outer.clone());
+        inner.define(String::from("a"), LoxValue::Nil);
+        inner.define(String::from("b"), LoxValue::Nil);
+
+        assert_eq!(globals.borrow().depth(), 0);
+        assert_eq!(outer.borrow().depth(), 1);
+        assert_eq!(inner.depth(), 2);
+        assert_eq!(inner.local_count(), 2);
+        assert_eq!(outer.borrow().local_count(), 0);
+    }
+}
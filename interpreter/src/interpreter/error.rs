@@ -1,4 +1,5 @@
 use super::LoxValue;
+use super::callable::Arity;
 use std::fmt::{Display, Formatter};
 
 #[derive(Debug)]
@@ -13,13 +14,61 @@ pub enum InterpreterErrorType {
     WrongBinaryOperands(LoxValue, syntax::token::TokenType, LoxValue),
     DivisionByZero,
     UndefinedVariable(String),
+    /// An undefined name that matches a method of the instance the code runs in.
+    MethodWithoutThis(String),
+    AccessBeforeInitialization {
+        name: String,
+        declaration_line: Option<usize>,
+    },
     NotACallable,
-    WrongArity { original: usize, user: usize },
+    /// A call to a property whose value is not callable, such as a data field.
+    NotACallableProperty {
+        owner: String,
+        field: String,
+        type_name: &'static str,
+    },
+    WrongArity {
+        original: Arity,
+        user: usize,
+    },
     Native(NativeError),
     NotInLoop,
     InvalidInstance(String),
-    NotAProperty { class_name: String, field: String },
+    NotAProperty {
+        class_name: String,
+        field: String,
+    },
+    NotAStaticField {
+        class_name: String,
+        field: String,
+    },
+    FrozenInstance {
+        class_name: String,
+        field: String,
+    },
+    /// A property access on a builtin value, such as a string, that has no such property.
+    NotABuiltinProperty {
+        type_name: &'static str,
+        field: String,
+    },
     InvalidSuperClass,
+    /// Source given to [`Interpreter::eval`](super::Interpreter::eval) that is not a single
+    /// valid expression.
+    InvalidExpression(String),
+    InvalidRepetitionCount(f64),
+    /// A subscript, such as `value[0]`, on a value that is not bytes.
+    NotIndexable(&'static str),
+    /// A subscript that is not an integer within the length of the bytes.
+    InvalidIndex {
+        index: LoxValue,
+        length: usize,
+    },
+    /// Raised by the `exit` native to unwind out of the script. It is not an actual error, and
+    /// [`Interpreter::interpret`](super::Interpreter::interpret) turns it into an exit code.
+    Exit(u8),
+    /// A value thrown by a `throw` statement that left a function, or the script, without
+    /// being caught.
+    Thrown(LoxValue),
 }
 
 pub type InterpreterResult<T> = Result<T, Box<InterpreterError>>;
@@ -32,13 +81,81 @@ pub enum NativeError {
     NumParse(#[from] std::num::ParseFloatError),
     #[error("System Time Error: {0}")]
     SystemTime(#[from] std::time::SystemTimeError),
+    #[error("Format placeholder {{{0}}} has no matching argument")]
+    FormatArgument(usize),
+    #[error("Invalid format template: {0}")]
+    FormatTemplate(String),
+    #[error("Invalid duration: {0}")]
+    Duration(#[from] std::time::TryFromFloatSecsError),
+    #[error("Assertion failed: expected {expected}, got {actual}")]
+    AssertionFailed { expected: String, actual: String },
+    #[error("Assertion failed: expected an error, but none was raised")]
+    NothingThrown,
+    #[error("Exit code {0} is not an integer between 0 and 255")]
+    InvalidExitCode(f64),
+    #[error("Division by zero in mod")]
+    ModuloByZero,
+    #[error("{0} is not a valid code point")]
+    InvalidCodePoint(f64),
+    #[error("Parameter in ord must be a single character, got {0:?}")]
+    NotACharacter(String),
+    #[error("{0} is not a valid hex string")]
+    InvalidHex(String),
+    #[error("{index} is not a valid index into {length} bytes")]
+    InvalidByteIndex { index: f64, length: usize },
+    /// A number of decimal places that `function` cannot format or round to.
+    #[error("The number of digits in {function} must be {expected}, got {digits}")]
+    InvalidDigits {
+        function: &'static str,
+        expected: &'static str,
+        digits: f64,
+    },
+    /// An argument of the wrong type. `index` starts at 1.
+    #[error("Argument {index} to {function} expected {expected}, got {got}")]
+    BadArgument {
+        function: &'static str,
+        index: usize,
+        expected: &'static str,
+        got: &'static str,
+    },
 }
 
 pub type NativeResult<T> = Result<T, NativeError>;
 
-impl Display for InterpreterError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let err_message = match &self.error_type {
+/// A [`LoxValue`] converted into a Rust type that it does not hold.
+#[derive(Debug, PartialEq, thiserror::Error)]
+#[error("Expected {expected}, got {got}")]
+pub struct ConversionError {
+    pub expected: &'static str,
+    pub got: &'static str,
+}
+
+/// An error found while loading a prelude, at whichever stage it was found.
+#[derive(Debug, thiserror::Error)]
+pub enum PreludeError {
+    #[error("Syntax Error: {0}")]
+    Scan(syntax::ScannerError),
+    #[error("{0}")]
+    Parse(#[from] Box<syntax::parser::ParserError>),
+    #[error("{0}")]
+    Fold(#[from] syntax::fold::FoldError),
+    #[error("Resolver error: {0}")]
+    Resolve(#[from] crate::resolver::ResolverError),
+    #[error("{0}")]
+    Runtime(#[from] Box<InterpreterError>),
+}
+
+/* The scanner error does not implement `Error`, so thiserror cannot derive this conversion */
+impl From<syntax::ScannerError> for PreludeError {
+    fn from(error: syntax::ScannerError) -> Self {
+        Self::Scan(error)
+    }
+}
+
+impl InterpreterError {
+    /// Describes the error, without the line it happened in.
+    pub fn message(&self) -> String {
+        match &self.error_type {
             InterpreterErrorType::WrongUnaryOperands(op, t) => {
                 format!("The unary operation {op:?} is not valid over token of type: {t}")
             }
@@ -51,24 +168,54 @@ impl Display for InterpreterError {
             InterpreterErrorType::UndefinedVariable(name) => {
                 format!("Variable {name} is undefined")
             }
+            InterpreterErrorType::MethodWithoutThis(name) => {
+                format!("Variable {name} is undefined. Did you mean this.{name}()?")
+            }
+            InterpreterErrorType::AccessBeforeInitialization {
+                name,
+                declaration_line: Some(line),
+            } => {
+                format!("Variable {name} was used before its declaration in line {line}")
+            }
+            InterpreterErrorType::AccessBeforeInitialization {
+                name,
+                declaration_line: None,
+            } => {
+                format!("Variable {name} was used before it was declared")
+            }
             InterpreterErrorType::NotACallable => {
                 format!(
                     "Value {} at line {} is not a callable",
-                    self.token.lexeme(),
+                    self.token.display_lexeme(),
                     self.token.line()
                 )
             }
+            InterpreterErrorType::NotACallableProperty {
+                owner,
+                field,
+                type_name,
+            } => {
+                let article = if type_name.starts_with(['a', 'e', 'i', 'o', 'u']) {
+                    "an"
+                } else {
+                    "a"
+                };
+                format!("Property '{field}' of {owner} is {article} {type_name}, not callable")
+            }
             InterpreterErrorType::WrongArity { original, user } => {
                 format!(
                     "Function {} called with {user} arguments, but required {original}",
-                    self.token.lexeme()
+                    self.token.display_lexeme()
                 )
             }
             InterpreterErrorType::Native(err) => {
                 format!("Native Error - {err}")
             }
             InterpreterErrorType::NotInLoop => {
-                format!("Used {} statement outside a loop", self.token.lexeme())
+                format!(
+                    "Used {} statement outside a loop",
+                    self.token.display_lexeme()
+                )
             }
             InterpreterErrorType::InvalidInstance(name) => {
                 format!("Identifier {name} is not an instance")
@@ -79,10 +226,37 @@ impl Display for InterpreterError {
             } => {
                 format!("Class instance {instance} does not have a property called '{field}'")
             }
+            InterpreterErrorType::NotAStaticField { class_name, field } => {
+                format!("Class {class_name} does not have a static field called '{field}'")
+            }
+            InterpreterErrorType::NotABuiltinProperty { type_name, field } => {
+                format!("Value of type {type_name} does not have a property called '{field}'")
+            }
+            InterpreterErrorType::FrozenInstance { class_name, field } => {
+                format!("Cannot set '{field}' on a frozen instance of {class_name}")
+            }
             InterpreterErrorType::InvalidSuperClass => String::from("Superclass must be a class."),
-        };
+            InterpreterErrorType::InvalidExpression(reason) => {
+                format!("Invalid expression: {reason}")
+            }
+            InterpreterErrorType::InvalidRepetitionCount(count) => {
+                format!("A string cannot be repeated {count} times")
+            }
+            InterpreterErrorType::NotIndexable(type_name) => {
+                format!("Value of type {type_name} cannot be indexed")
+            }
+            InterpreterErrorType::InvalidIndex { index, length } => {
+                format!("{index} is not a valid index into {length} bytes")
+            }
+            InterpreterErrorType::Exit(code) => format!("Script exited with code {code}"),
+            InterpreterErrorType::Thrown(value) => format!("Uncaught exception: {value}"),
+        }
+    }
+}
 
-        write!(f, "{err_message}\n[line {}]", self.token.line())
+impl Display for InterpreterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\n[line {}]", self.message(), self.token.line())
     }
 }
 
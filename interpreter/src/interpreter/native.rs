@@ -1,7 +1,65 @@
-use crate::interpreter::{LoxValue, NativeResult};
+use crate::interpreter::{
+    Interpreter, InterpreterError, InterpreterErrorType, InterpreterResult, LoxValue, NativeError,
+    NativeResult,
+};
 use rand::Rng;
 use std::rc::Rc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
+use syntax::Token;
+
+/// Returns the number at `args[index]`, or a [`NativeError::BadArgument`] blaming `function`.
+fn number_argument(function: &'static str, args: &[LoxValue], index: usize) -> NativeResult<f64> {
+    match &args[index] {
+        LoxValue::Number(n) => Ok(*n),
+        other => Err(bad_argument(function, index, "number", other)),
+    }
+}
+
+/// Returns the string at `args[index]`, or a [`NativeError::BadArgument`] blaming `function`.
+fn string_argument<'a>(
+    function: &'static str,
+    args: &'a [LoxValue],
+    index: usize,
+) -> NativeResult<&'a Rc<String>> {
+    match &args[index] {
+        LoxValue::String(string) => Ok(string),
+        other => Err(bad_argument(function, index, "string", other)),
+    }
+}
+
+/// Returns the bytes at `args[index]`, or a [`NativeError::BadArgument`] blaming `function`.
+fn bytes_argument<'a>(
+    function: &'static str,
+    args: &'a [LoxValue],
+    index: usize,
+) -> NativeResult<&'a Rc<Vec<u8>>> {
+    match &args[index] {
+        LoxValue::Bytes(bytes) => Ok(bytes),
+        other => Err(bad_argument(function, index, "bytes", other)),
+    }
+}
+
+fn bad_argument(
+    function: &'static str,
+    index: usize,
+    expected: &'static str,
+    got: &LoxValue,
+) -> NativeError {
+    NativeError::BadArgument {
+        function,
+        index: index + 1,
+        expected,
+        got: got.type_name(),
+    }
+}
+
+/// Wraps an error of a native method, which reports it at the call site.
+fn native_error(error: NativeError, token: &Token) -> Box<InterpreterError> {
+    Box::new(InterpreterError {
+        error_type: InterpreterErrorType::Native(error),
+        token: token.clone(),
+    })
+}
 
 pub(super) fn clock(_args: &[LoxValue]) -> NativeResult<LoxValue> {
     let time = SystemTime::now();
@@ -10,24 +68,33 @@ pub(super) fn clock(_args: &[LoxValue]) -> NativeResult<LoxValue> {
     Ok(LoxValue::Number(unix_time.as_secs_f64()))
 }
 
-pub(super) fn read_line(_args: &[LoxValue]) -> NativeResult<LoxValue> {
-    let stdin = std::io::stdin();
-    let mut line = String::new();
+pub(super) fn read_line(
+    interpreter: &Interpreter,
+    _args: &[LoxValue],
+    token: &Token,
+) -> InterpreterResult<LoxValue> {
+    match interpreter.read_input_line() {
+        Ok(line) => Ok(LoxValue::String(Rc::new(line))),
+        Err(e) => Err(native_error(e.into(), token)),
+    }
+}
 
-    stdin.read_line(&mut line)?;
-    line.pop();
+/// Writes the prompt, without a newline, and reads a line like `read_line` does.
+pub(super) fn input(
+    interpreter: &Interpreter,
+    args: &[LoxValue],
+    token: &Token,
+) -> InterpreterResult<LoxValue> {
+    let prompt = string_argument("input", args, 0).map_err(|e| native_error(e, token))?;
 
-    Ok(LoxValue::String(Rc::new(line)))
+    interpreter.write_output(format_args!("{prompt}"));
+    interpreter.flush_output();
+    read_line(interpreter, &[], token)
 }
 
 pub(super) fn random(args: &[LoxValue]) -> NativeResult<LoxValue> {
-    let (mut inf, mut sup) = match (&args[0], &args[1]) {
-        (LoxValue::Number(a), LoxValue::Number(b)) => (*a as i64, *b as i64),
-        _ => {
-            eprintln!("Parameters in random must be numbers");
-            return Ok(LoxValue::Nil);
-        }
-    };
+    let mut inf = number_argument("random", args, 0)? as i64;
+    let mut sup = number_argument("random", args, 1)? as i64;
 
     if inf > sup {
         std::mem::swap(&mut inf, &mut sup);
@@ -40,14 +107,624 @@ pub(super) fn random(args: &[LoxValue]) -> NativeResult<LoxValue> {
 }
 
 pub(super) fn string_to_number(args: &[LoxValue]) -> NativeResult<LoxValue> {
-    let source = match &args[0] {
-        LoxValue::String(str) => str.trim(),
-        _ => {
-            eprintln!("Argument is not a number");
-            return Ok(LoxValue::Nil);
+    let source = string_argument("string_to_number", args, 0)?;
+
+    let num: f64 = source.trim().parse()?;
+    Ok(LoxValue::Number(num))
+}
+
+/// Lenient version of [`string_to_number`], which returns `nil` when the argument cannot be
+/// parsed instead of raising an error.
+pub(super) fn parse_number(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let number = match &args[0] {
+        LoxValue::String(str) => str.trim().parse().ok(),
+        _ => None,
+    };
+
+    Ok(number.map_or(LoxValue::Nil, LoxValue::Number))
+}
+
+/// Returns whether `x` is within the inclusive range delimited by the two bounds, which may be
+/// given in any order.
+pub(super) fn between(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let x = number_argument("between", args, 0)?;
+    let mut low = number_argument("between", args, 1)?;
+    let mut high = number_argument("between", args, 2)?;
+
+    if low > high {
+        std::mem::swap(&mut low, &mut high);
+    }
+
+    Ok(LoxValue::Boolean(low <= x && x <= high))
+}
+
+/// Blocks the script for the given number of seconds, which may be fractional.
+pub(super) fn sleep(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let seconds = number_argument("sleep", args, 0)?;
+
+    std::thread::sleep(Duration::try_from_secs_f64(seconds)?);
+    Ok(LoxValue::Nil)
+}
+
+/// Euclidean modulo: the result is always non-negative, so `mod(-1, 3) == 2`. This makes it
+/// suitable for wrapping indices around.
+pub(super) fn modulo(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let a = number_argument("mod", args, 0)?;
+    let b = number_argument("mod", args, 1)?;
+
+    if b == 0.0 {
+        return Err(NativeError::ModuloByZero);
+    }
+
+    Ok(LoxValue::Number(a.rem_euclid(b)))
+}
+
+/// Returns a string holding the single character with the given Unicode code point. Values above
+/// `0x10FFFF`, surrogates and non-integers are rejected.
+pub(super) fn chr(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let code = number_argument("chr", args, 0)?;
+
+    let character = (code.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&code))
+        .then(|| char::from_u32(code as u32))
+        .flatten();
+    match character {
+        Some(character) => Ok(LoxValue::String(Rc::new(character.to_string()))),
+        None => Err(NativeError::InvalidCodePoint(code)),
+    }
+}
+
+/// Encodes bytes as a string of lowercase hexadecimal digits, two per byte.
+pub(super) fn to_hex(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let bytes = bytes_argument("to_hex", args, 0)?;
+
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    Ok(LoxValue::String(Rc::new(hex)))
+}
+
+/// Decodes a string of hexadecimal digits, two per byte, into bytes.
+pub(super) fn from_hex(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let hex = string_argument("from_hex", args, 0)?;
+
+    if hex.len() % 2 != 0 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err(NativeError::InvalidHex(hex.to_string()));
+    }
+
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect();
+    Ok(LoxValue::Bytes(Rc::new(bytes)))
+}
+
+/// Returns the byte at index `i` as a number from 0 to 255, the same as `bytes[i]`.
+pub(super) fn byte_at(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let bytes = bytes_argument("byte_at", args, 0)?;
+    let index = number_argument("byte_at", args, 1)?;
+
+    match byte(bytes, index) {
+        Some(byte) => Ok(byte),
+        None => Err(NativeError::InvalidByteIndex {
+            index,
+            length: bytes.len(),
+        }),
+    }
+}
+
+/// Returns the byte at `index` as a number, if the index is an integer within the bytes.
+pub(super) fn byte(bytes: &[u8], index: f64) -> Option<LoxValue> {
+    (index.fract() == 0.0 && index >= 0.0)
+        .then(|| bytes.get(index as usize))
+        .flatten()
+        .map(|byte| LoxValue::Number(f64::from(*byte)))
+}
+
+/// Reads the whole file at the given path as bytes.
+pub(super) fn read_file_bytes(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let path = string_argument("read_file_bytes", args, 0)?;
+    Ok(LoxValue::Bytes(Rc::new(std::fs::read(path.as_str())?)))
+}
+
+/// Hashes a string, number or bytes with 64-bit FNV-1a, and returns the hash as 16 hex digits.
+/// The hash is the same on every run and platform, so it can be stored or compared across runs.
+/// Numbers hash their IEEE-754 bits, with `-0` hashed as `0` and every `NaN` alike, the same
+/// way `==` treats them.
+pub(super) fn hash(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let number_bits;
+    let bytes: &[u8] = match &args[0] {
+        LoxValue::String(string) => string.as_bytes(),
+        LoxValue::Bytes(bytes) => bytes,
+        LoxValue::Number(n) => {
+            let n = match *n {
+                0.0 => 0.0,
+                n if n.is_nan() => f64::NAN,
+                n => n,
+            };
+            number_bits = n.to_bits().to_le_bytes();
+            &number_bits
         }
+        other => return Err(bad_argument("hash", 0, "string, number or bytes", other)),
     };
 
-    let num: f64 = source.parse()?;
-    Ok(LoxValue::Number(num))
+    let hash = bytes.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    });
+    Ok(LoxValue::String(Rc::new(format!("{hash:016x}"))))
+}
+
+/// Returns the Unicode code point of the only character of a string.
+pub(super) fn ord(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let string = string_argument("ord", args, 0)?;
+
+    let mut chars = string.chars();
+    match (chars.next(), chars.next()) {
+        (Some(character), None) => Ok(LoxValue::Number(character as u32 as f64)),
+        _ => Err(NativeError::NotACharacter(string.to_string())),
+    }
+}
+
+/// Returns positive infinity. Negative infinity is `-inf()`.
+pub(super) fn inf(_args: &[LoxValue]) -> NativeResult<LoxValue> {
+    Ok(LoxValue::Number(f64::INFINITY))
+}
+
+/// Returns `NaN`, the result of undefined operations such as `0 / 0` with IEEE division.
+pub(super) fn nan(_args: &[LoxValue]) -> NativeResult<LoxValue> {
+    Ok(LoxValue::Number(f64::NAN))
+}
+
+/// Returns whether the argument is `NaN`, which is the only value not equal to itself. Values
+/// that are not numbers are never `NaN`.
+pub(super) fn is_nan(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let nan = matches!(args[0], LoxValue::Number(n) if n.is_nan());
+    Ok(LoxValue::Boolean(nan))
+}
+
+/// Rounds `x` to the given number of decimal places, so `round_to(3.14159, 2) == 3.14`.
+pub(super) fn round_to(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let x = number_argument("round_to", args, 0)?;
+    let digits = number_argument("round_to", args, 1)?;
+
+    if digits < 0.0 || digits.fract() != 0.0 {
+        return Err(NativeError::InvalidDigits {
+            function: "round_to",
+            expected: "a non-negative integer",
+            digits,
+        });
+    }
+
+    let scale = 10f64.powi(digits as i32);
+    Ok(LoxValue::Number((x * scale).round() / scale))
+}
+
+/// The most digits `to_fixed` and `to_exponential` accept, as in JavaScript.
+const MAX_FORMAT_DIGITS: f64 = 100.0;
+
+/// Returns `digits` as a count of digits for `function`, or an error if it is not one.
+fn format_digits(function: &'static str, digits: f64) -> NativeResult<usize> {
+    if !(0.0..=MAX_FORMAT_DIGITS).contains(&digits) || digits.fract() != 0.0 {
+        return Err(NativeError::InvalidDigits {
+            function,
+            expected: "an integer between 0 and 100",
+            digits,
+        });
+    }
+    Ok(digits as usize)
+}
+
+/// Formats `x` with the given number of decimal places, so `to_fixed(3.14159, 2) == "3.14"`.
+pub(super) fn to_fixed(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let x = number_argument("to_fixed", args, 0)?;
+    let digits = number_argument("to_fixed", args, 1)?;
+
+    let digits = format_digits("to_fixed", digits)?;
+    Ok(LoxValue::String(Rc::new(format!("{x:.digits$}"))))
+}
+
+/// Formats `x` in scientific notation with the given number of decimal places, so
+/// `to_exponential(12345, 2) == "1.23e4"`.
+pub(super) fn to_exponential(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let x = number_argument("to_exponential", args, 0)?;
+    let digits = number_argument("to_exponential", args, 1)?;
+
+    let digits = format_digits("to_exponential", digits)?;
+    Ok(LoxValue::String(Rc::new(format!("{x:.digits$e}"))))
+}
+
+pub(super) fn to_string(
+    interpreter: &Interpreter,
+    args: &[LoxValue],
+    token: &Token,
+) -> InterpreterResult<LoxValue> {
+    let string = interpreter.stringify(&args[0], token)?;
+    Ok(LoxValue::String(Rc::new(string)))
+}
+
+/// Writes the value to the standard error, without a newline.
+pub(super) fn eprint(
+    interpreter: &Interpreter,
+    args: &[LoxValue],
+    token: &Token,
+) -> InterpreterResult<LoxValue> {
+    let string = interpreter.stringify(&args[0], token)?;
+    interpreter.write_error_output(format_args!("{string}"));
+    Ok(LoxValue::Nil)
+}
+
+/// Writes the value to the standard error, followed by a newline.
+pub(super) fn eprintln(
+    interpreter: &Interpreter,
+    args: &[LoxValue],
+    token: &Token,
+) -> InterpreterResult<LoxValue> {
+    let string = interpreter.stringify(&args[0], token)?;
+    interpreter.write_error_output(format_args!("{string}\n"));
+    Ok(LoxValue::Nil)
+}
+
+/// Writes how deep the environment of the caller is nested, and how many variables it holds, to
+/// the standard error. Runaway closures and scopes show up as an ever growing depth.
+pub(super) fn debug_env(
+    interpreter: &Interpreter,
+    _args: &[LoxValue],
+    token: &Token,
+) -> InterpreterResult<LoxValue> {
+    let environment = interpreter.current_environment();
+    let environment = environment.borrow();
+    interpreter.write_error_output(format_args!(
+        "[line {}] Environment depth: {}, with {} variables\n",
+        token.line(),
+        environment.depth(),
+        environment.local_count()
+    ));
+    Ok(LoxValue::Nil)
+}
+
+pub(super) fn repr(
+    interpreter: &Interpreter,
+    args: &[LoxValue],
+    _token: &Token,
+) -> InterpreterResult<LoxValue> {
+    Ok(LoxValue::String(Rc::new(interpreter.reprify(&args[0]))))
+}
+
+/// Replaces the `{0}`, `{1}`... placeholders of the template, which is the first argument, with
+/// the rest of the arguments converted to strings. `{{` and `}}` stand for literal braces.
+pub(super) fn format(
+    interpreter: &Interpreter,
+    args: &[LoxValue],
+    token: &Token,
+) -> InterpreterResult<LoxValue> {
+    let template = string_argument("format", args, 0).map_err(|e| native_error(e, token))?;
+    let native_error = |error| Err(native_error(error, token));
+
+    let mut result = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                result.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                result.push('}');
+            }
+            '{' => {
+                let Some((index, rest)) = chars.as_str().split_once('}') else {
+                    return native_error(NativeError::FormatTemplate(template.to_string()));
+                };
+                let Ok(index) = index.parse::<usize>() else {
+                    return native_error(NativeError::FormatTemplate(template.to_string()));
+                };
+                let Some(argument) = args[1..].get(index) else {
+                    return native_error(NativeError::FormatArgument(index));
+                };
+
+                result.push_str(&interpreter.stringify(argument, token)?);
+                chars = rest.chars();
+            }
+            '}' => return native_error(NativeError::FormatTemplate(template.to_string())),
+            c => result.push(c),
+        }
+    }
+
+    Ok(LoxValue::String(Rc::new(result)))
+}
+
+/// Freezes an instance, so assigning to its fields raises an error from then on. Returns the
+/// instance itself, which allows `var point = freeze(Point(1, 2));`.
+pub(super) fn freeze(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let LoxValue::Instance(instance) = &args[0] else {
+        return Err(bad_argument("freeze", 0, "instance", &args[0]));
+    };
+
+    instance.freeze();
+    Ok(args[0].clone())
+}
+
+/// Returns the names of the fields of an instance, in the order they were first set, as a
+/// comma-separated string.
+pub(super) fn fields(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    let LoxValue::Instance(instance) = &args[0] else {
+        return Err(bad_argument("fields", 0, "instance", &args[0]));
+    };
+
+    Ok(LoxValue::String(Rc::new(instance.field_names().join(", "))))
+}
+
+/// Compares two values structurally, see [`LoxValue::deep_equals`].
+pub(super) fn deep_equals(args: &[LoxValue]) -> NativeResult<LoxValue> {
+    Ok(LoxValue::Boolean(args[0].deep_equals(&args[1])))
+}
+
+/// Raises an error showing both values unless they are equal by [`LoxValue::deep_equals`],
+/// which makes Lox scripts usable as their own test suites.
+pub(super) fn assert_eq(
+    interpreter: &Interpreter,
+    args: &[LoxValue],
+    token: &Token,
+) -> InterpreterResult<LoxValue> {
+    let (expected, actual) = (&args[0], &args[1]);
+    if expected.deep_equals(actual) {
+        return Ok(LoxValue::Nil);
+    }
+
+    let error = NativeError::AssertionFailed {
+        expected: interpreter.stringify(expected, token)?,
+        actual: interpreter.stringify(actual, token)?,
+    };
+    Err(native_error(error, token))
+}
+
+/// Calls the given function with no arguments and passes if it raises a runtime error, which
+/// is discarded. An `exit` inside the function still stops the script. A function that takes
+/// arguments is rejected up front, since calling it would raise an arity error that passes.
+pub(super) fn assert_throws(
+    interpreter: &Interpreter,
+    args: &[LoxValue],
+    token: &Token,
+) -> InterpreterResult<LoxValue> {
+    let LoxValue::Callable(callable) = &args[0] else {
+        return Err(native_error(
+            bad_argument("assert_throws", 0, "function", &args[0]),
+            token,
+        ));
+    };
+    if !callable.arity().accepts(0) {
+        let error = NativeError::BadArgument {
+            function: "assert_throws",
+            index: 1,
+            expected: "function without parameters",
+            got: "function with parameters",
+        };
+        return Err(native_error(error, token));
+    }
+
+    match interpreter.interpret_call(callable.clone(), vec![], token) {
+        Ok(_) => Err(native_error(NativeError::NothingThrown, token)),
+        Err(error) if matches!(error.error_type, InterpreterErrorType::Exit(_)) => Err(error),
+        Err(_) => Ok(LoxValue::Nil),
+    }
+}
+
+/// Stops the script with the given exit code, which must fit in a byte, as process exit codes
+/// do. Natives cannot unwind the interpreter by themselves, so this raises an
+/// [`InterpreterErrorType::Exit`] that `interpret` catches.
+pub(super) fn exit(
+    _interpreter: &Interpreter,
+    args: &[LoxValue],
+    token: &Token,
+) -> InterpreterResult<LoxValue> {
+    let code = number_argument("exit", args, 0).map_err(|e| native_error(e, token))?;
+    if !(0.0..=255.0).contains(&code) || code.fract() != 0.0 {
+        return Err(native_error(NativeError::InvalidExitCode(code), token));
+    }
+
+    Err(Box::new(InterpreterError {
+        error_type: InterpreterErrorType::Exit(code as u8),
+        token: token.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::{LoxValue, NativeError};
+    use std::rc::Rc;
+
+    fn string(value: &str) -> LoxValue {
+        LoxValue::String(Rc::new(String::from(value)))
+    }
+
+    #[test]
+    fn parse_number() {
+        assert_eq!(
+            super::parse_number(&[string("3.5")]).unwrap(),
+            LoxValue::Number(3.5)
+        );
+        assert_eq!(
+            super::parse_number(&[string(" 42 ")]).unwrap(),
+            LoxValue::Number(42.0)
+        );
+        assert_eq!(
+            super::parse_number(&[string("abc")]).unwrap(),
+            LoxValue::Nil
+        );
+        assert_eq!(
+            super::parse_number(&[LoxValue::Boolean(true)]).unwrap(),
+            LoxValue::Nil
+        );
+    }
+
+    #[test]
+    fn between() {
+        let between = |x, low, high| {
+            super::between(&[
+                LoxValue::Number(x),
+                LoxValue::Number(low),
+                LoxValue::Number(high),
+            ])
+            .unwrap()
+        };
+
+        assert_eq!(between(5.0, 1.0, 10.0), LoxValue::Boolean(true));
+        assert_eq!(between(1.0, 1.0, 10.0), LoxValue::Boolean(true));
+        assert_eq!(between(10.0, 1.0, 10.0), LoxValue::Boolean(true));
+        assert_eq!(between(10.5, 1.0, 10.0), LoxValue::Boolean(false));
+        assert_eq!(between(0.0, 1.0, 10.0), LoxValue::Boolean(false));
+        assert_eq!(between(5.0, 10.0, 1.0), LoxValue::Boolean(true));
+        assert_eq!(between(11.0, 10.0, 1.0), LoxValue::Boolean(false));
+
+        let result = super::between(&[LoxValue::Number(5.0), string("1"), LoxValue::Number(10.0)]);
+        assert!(matches!(
+            result,
+            Err(NativeError::BadArgument {
+                function: "between",
+                index: 2,
+                expected: "number",
+                got: "string",
+            })
+        ));
+    }
+
+    #[test]
+    fn modulo() {
+        let modulo = |a, b| super::modulo(&[LoxValue::Number(a), LoxValue::Number(b)]);
+
+        assert_eq!(modulo(7.0, 3.0).unwrap(), LoxValue::Number(1.0));
+        assert_eq!(modulo(-1.0, 3.0).unwrap(), LoxValue::Number(2.0));
+        assert_eq!(modulo(-7.0, 3.0).unwrap(), LoxValue::Number(2.0));
+        assert_eq!(modulo(-6.0, 3.0).unwrap(), LoxValue::Number(0.0));
+        assert_eq!(modulo(7.0, -3.0).unwrap(), LoxValue::Number(1.0));
+        assert_eq!(modulo(-7.5, 2.0).unwrap(), LoxValue::Number(0.5));
+        assert!(matches!(modulo(1.0, 0.0), Err(NativeError::ModuloByZero)));
+    }
+
+    #[test]
+    fn round_to() {
+        let round_to =
+            |x, digits| super::round_to(&[LoxValue::Number(x), LoxValue::Number(digits)]);
+
+        assert_eq!(round_to(1.23456, 2.0).unwrap(), LoxValue::Number(1.23));
+        assert_eq!(round_to(2.5, 0.0).unwrap(), LoxValue::Number(3.0));
+        assert_eq!(round_to(-1.2345, 3.0).unwrap(), LoxValue::Number(-1.235));
+        assert!(matches!(
+            round_to(1.23456, -1.0),
+            Err(NativeError::InvalidDigits { digits: -1.0, .. })
+        ));
+        assert!(matches!(
+            round_to(1.23456, 1.5),
+            Err(NativeError::InvalidDigits { digits: 1.5, .. })
+        ));
+    }
+
+    #[test]
+    fn chr_and_ord() {
+        let chr = |code| super::chr(&[LoxValue::Number(code)]);
+        let ord = |s| super::ord(&[string(s)]);
+
+        assert_eq!(ord("A").unwrap(), LoxValue::Number(65.0));
+        assert_eq!(chr(65.0).unwrap(), string("A"));
+        assert_eq!(ord("é").unwrap(), LoxValue::Number(233.0));
+        assert_eq!(chr(0x1F600 as f64).unwrap(), string("😀"));
+        assert_eq!(ord("😀").unwrap(), LoxValue::Number(0x1F600 as f64));
+
+        for code in [0x110000 as f64, 0xD800 as f64, -1.0, 65.5] {
+            assert!(matches!(chr(code), Err(NativeError::InvalidCodePoint(_))));
+        }
+        assert!(matches!(ord(""), Err(NativeError::NotACharacter(_))));
+        assert!(matches!(ord("AB"), Err(NativeError::NotACharacter(_))));
+    }
+
+    #[test]
+    fn string_to_number_is_strict() {
+        assert!(super::string_to_number(&[string("abc")]).is_err());
+    }
+
+    #[test]
+    fn sleep() {
+        let start = std::time::Instant::now();
+        assert_eq!(
+            super::sleep(&[LoxValue::Number(0.0)]).unwrap(),
+            LoxValue::Nil
+        );
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+
+        assert!(super::sleep(&[LoxValue::Number(-1.0)]).is_err());
+    }
+
+    #[test]
+    fn hex_round_trips_bytes() {
+        let bytes = super::from_hex(&[string("00ff10Ab")]).unwrap();
+        assert_eq!(
+            bytes,
+            LoxValue::Bytes(Rc::new(vec![0x00, 0xff, 0x10, 0xab]))
+        );
+        assert_eq!(
+            super::to_hex(std::slice::from_ref(&bytes)).unwrap(),
+            string("00ff10ab")
+        );
+
+        let byte_at = |i| super::byte_at(&[bytes.clone(), LoxValue::Number(i)]);
+        assert_eq!(byte_at(1.0).unwrap(), LoxValue::Number(255.0));
+        assert!(matches!(
+            byte_at(4.0),
+            Err(NativeError::InvalidByteIndex { length: 4, .. })
+        ));
+        assert!(matches!(
+            byte_at(0.5),
+            Err(NativeError::InvalidByteIndex { length: 4, .. })
+        ));
+
+        for hex in ["abc", "zz", "+1"] {
+            assert!(matches!(
+                super::from_hex(&[string(hex)]),
+                Err(NativeError::InvalidHex(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn read_file_bytes_reads_binary_files() {
+        let path = std::env::temp_dir().join(format!("lox-bytes-{}.bin", std::process::id()));
+        std::fs::write(&path, [0x89, b'P', b'N', b'G', 0x00]).unwrap();
+
+        let bytes = super::read_file_bytes(&[string(path.to_str().unwrap())]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(bytes.to_string(), "<bytes(5) 89 50 4e 47 00>");
+        assert!(matches!(
+            super::read_file_bytes(&[string("/nonexistent/file")]),
+            Err(NativeError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn hash_is_fnv_1a() {
+        let hash = |value| super::hash(&[value]).unwrap();
+
+        assert_eq!(hash(string("")), string("cbf29ce484222325"));
+        assert_eq!(hash(string("a")), string("af63dc4c8601ec8c"));
+        assert_eq!(hash(string("foobar")), string("85944171f73967e8"));
+
+        assert_eq!(hash(string("shard")), hash(string("shard")));
+        assert_ne!(hash(string("shard")), hash(string("shards")));
+        assert_eq!(hash(LoxValue::Number(1.5)), hash(LoxValue::Number(1.5)));
+        assert_eq!(hash(LoxValue::Number(-0.0)), hash(LoxValue::Number(0.0)));
+        assert_eq!(
+            hash(LoxValue::Number(f64::NAN)),
+            hash(LoxValue::Number(-f64::NAN))
+        );
+
+        assert!(matches!(
+            super::hash(&[LoxValue::Nil]),
+            Err(NativeError::BadArgument {
+                index: 1,
+                expected: "string, number or bytes",
+                ..
+            })
+        ));
+    }
 }
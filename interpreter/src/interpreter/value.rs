@@ -1,15 +1,22 @@
+use crate::interpreter::ConversionError;
 use crate::interpreter::callable::Callable;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use indexmap::IndexMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
+/// How many bytes are shown when printing a `bytes` value.
+const BYTES_SHOWN: usize = 16;
+
 #[derive(Debug, Clone)]
 pub enum LoxValue {
     Nil,
     Boolean(bool),
     Number(f64),
     String(Rc<String>),
+    Bytes(Rc<Vec<u8>>),
     Callable(Rc<Callable>),
     Instance(Rc<Instance>),
 }
@@ -18,13 +25,19 @@ pub enum LoxValue {
 pub struct Class {
     name: String,
     methods: HashMap<String, Rc<Callable>>,
+    /// Methods already looked up through the superclass chain. Classes do not change after
+    /// they are declared, so entries never go stale.
+    method_cache: RefCell<HashMap<String, Option<Rc<Callable>>>>,
+    static_fields: RefCell<HashMap<String, LoxValue>>,
     super_class: Option<Rc<Class>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Instance {
     class: Rc<Class>,
-    fields: RefCell<HashMap<String, LoxValue>>,
+    /// Kept in insertion order, so enumerating the fields is the same on every run.
+    fields: RefCell<IndexMap<String, LoxValue>>,
+    frozen: Cell<bool>,
 }
 
 pub enum Field {
@@ -41,10 +54,160 @@ impl LoxValue {
             Self::Number(0.0) => false,
             Self::Number(_) => true,
             Self::String(_) => true,
+            Self::Bytes(_) => true,
             Self::Callable(_) => true,
             Self::Instance(_) => true,
         }
     }
+
+    /// The name of the type of the value, as shown in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Nil => "nil",
+            Self::Boolean(_) => "boolean",
+            Self::Number(_) => "number",
+            Self::String(_) => "string",
+            Self::Bytes(_) => "bytes",
+            Self::Callable(callable) => match &**callable {
+                Callable::Bound { .. } => "bound method",
+                _ => "function",
+            },
+            Self::Instance(_) => "instance",
+        }
+    }
+
+    /// Returns the class a value stands for, which is the case of class constructors.
+    pub fn as_class(&self) -> Option<&Rc<Class>> {
+        match self {
+            Self::Callable(callable) => match &**callable {
+                Callable::Constructor { class, .. } => Some(class),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Compares two values structurally: instances of the same class are equal if their fields
+    /// are, recursively. Any other values are compared with `==`.
+    pub fn deep_equals(&self, other: &LoxValue) -> bool {
+        self.deep_equals_visiting(other, &mut HashSet::new())
+    }
+
+    /// `visited` holds the pairs of instances being compared further up, which are assumed to
+    /// be equal when they are found again so reference cycles do not recurse forever.
+    fn deep_equals_visiting(
+        &self,
+        other: &LoxValue,
+        visited: &mut HashSet<(*const Instance, *const Instance)>,
+    ) -> bool {
+        let (Self::Instance(a), Self::Instance(b)) = (self, other) else {
+            return self == other;
+        };
+
+        if Rc::ptr_eq(a, b) || !visited.insert((Rc::as_ptr(a), Rc::as_ptr(b))) {
+            return true;
+        }
+        if !Rc::ptr_eq(&a.class, &b.class) {
+            return false;
+        }
+
+        let (a_fields, b_fields) = (a.fields.borrow().clone(), b.fields.borrow().clone());
+        a_fields.len() == b_fields.len()
+            && a_fields.iter().all(|(name, value)| {
+                b_fields
+                    .get(name)
+                    .is_some_and(|other| value.deep_equals_visiting(other, visited))
+            })
+    }
+}
+
+/// Numbers are compared with [`ordered_float::OrderedFloat`] semantics, so that every `NaN` is
+/// equal to each other and `-0.0 == 0.0`, which keeps `Eq` reflexive and consistent with `Hash`.
+/// Callables and instances are compared by identity.
+impl PartialEq for LoxValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Nil, Self::Nil) => true,
+            (Self::Boolean(a), Self::Boolean(b)) => a == b,
+            (Self::Number(a), Self::Number(b)) => {
+                ordered_float::OrderedFloat(*a) == ordered_float::OrderedFloat(*b)
+            }
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Bytes(a), Self::Bytes(b)) => a == b,
+            (Self::Callable(a), Self::Callable(b)) => Rc::ptr_eq(a, b),
+            (Self::Instance(a), Self::Instance(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for LoxValue {}
+
+impl Hash for LoxValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Nil => {}
+            Self::Boolean(b) => b.hash(state),
+            Self::Number(n) => ordered_float::OrderedFloat(*n).hash(state),
+            Self::String(str) => str.hash(state),
+            Self::Bytes(bytes) => bytes.hash(state),
+            Self::Callable(callable) => Rc::as_ptr(callable).hash(state),
+            Self::Instance(instance) => Rc::as_ptr(instance).hash(state),
+        }
+    }
+}
+
+impl From<f64> for LoxValue {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<bool> for LoxValue {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value)
+    }
+}
+
+impl From<String> for LoxValue {
+    fn from(value: String) -> Self {
+        Self::String(Rc::new(value))
+    }
+}
+
+impl From<&str> for LoxValue {
+    fn from(value: &str) -> Self {
+        Self::String(Rc::new(value.to_string()))
+    }
+}
+
+impl TryFrom<LoxValue> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(value: LoxValue) -> Result<Self, Self::Error> {
+        match value {
+            LoxValue::Number(n) => Ok(n),
+            other => Err(ConversionError {
+                expected: "number",
+                got: other.type_name(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<LoxValue> for String {
+    type Error = ConversionError;
+
+    fn try_from(value: LoxValue) -> Result<Self, Self::Error> {
+        match value {
+            LoxValue::String(string) => Ok(Rc::unwrap_or_clone(string)),
+            other => Err(ConversionError {
+                expected: "string",
+                got: other.type_name(),
+            }),
+        }
+    }
 }
 
 impl Display for LoxValue {
@@ -52,8 +215,21 @@ impl Display for LoxValue {
         match self {
             Self::Nil => write!(f, "nil"),
             Self::Boolean(b) => write!(f, "{b}"),
+            /* Rust writes NaN, but infinities as inf, so NaN is lowercased to match */
+            Self::Number(n) if n.is_nan() => f.write_str("nan"),
             Self::Number(n) => write!(f, "{n}"),
             Self::String(str) => f.write_str(str),
+            Self::Bytes(bytes) => {
+                /* Only the first bytes are shown, so printing a whole file stays readable */
+                write!(f, "<bytes({})", bytes.len())?;
+                for byte in bytes.iter().take(BYTES_SHOWN) {
+                    write!(f, " {byte:02x}")?;
+                }
+                if bytes.len() > BYTES_SHOWN {
+                    f.write_str(" ...")?;
+                }
+                f.write_str(">")
+            }
             Self::Callable(callable) => Debug::fmt(callable, f),
             Self::Instance(instance) => Display::fmt(instance, f),
         }
@@ -69,11 +245,53 @@ impl Class {
         Self {
             name,
             methods,
+            method_cache: RefCell::new(HashMap::new()),
+            static_fields: RefCell::new(HashMap::new()),
             super_class,
         }
     }
 
+    pub fn define_static(&self, name: String, value: LoxValue) {
+        self.static_fields.borrow_mut().insert(name, value);
+    }
+
+    /// Looks a static field up in the class, and then in its superclasses.
+    pub fn get_static(&self, name: &str) -> Option<LoxValue> {
+        match self.static_fields.borrow().get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.super_class.as_ref()?.get_static(name),
+        }
+    }
+
+    /// Assigns to the static field in the class or superclass that declares it. Returns `false`
+    /// if none of them does.
+    #[must_use]
+    pub fn set_static(&self, name: &str, value: LoxValue) -> bool {
+        if let Some(field) = self.static_fields.borrow_mut().get_mut(name) {
+            *field = value;
+            return true;
+        }
+
+        match &self.super_class {
+            Some(super_class) => super_class.set_static(name, value),
+            None => false,
+        }
+    }
+
     pub fn find_method(&self, name: &str) -> Option<Rc<Callable>> {
+        if let Some(method) = self.method_cache.borrow().get(name) {
+            return method.clone();
+        }
+
+        let method = self.lookup_method(name);
+        self.method_cache
+            .borrow_mut()
+            .insert(name.to_string(), method.clone());
+        method
+    }
+
+    /// Walks the superclass chain looking for the method, without the cache.
+    fn lookup_method(&self, name: &str) -> Option<Rc<Callable>> {
         self.methods
             .get(name)
             .cloned()
@@ -91,7 +309,8 @@ impl Instance {
     pub fn new(class: Rc<Class>) -> Self {
         Self {
             class,
-            fields: RefCell::new(HashMap::new()),
+            fields: RefCell::new(IndexMap::new()),
+            frozen: Cell::new(false),
         }
     }
 
@@ -105,8 +324,42 @@ impl Instance {
         }
     }
 
-    pub fn set(&self, key: &str, value: LoxValue) {
+    /// Sets a field of the instance. Returns `false` without changing anything if the instance
+    /// has been frozen.
+    #[must_use]
+    pub fn set(&self, key: &str, value: LoxValue) -> bool {
+        if self.frozen.get() {
+            return false;
+        }
+
         self.fields.borrow_mut().insert(key.to_string(), value);
+        true
+    }
+
+    /// Iterates over the fields along with their values, in the order they were first set. The
+    /// fields are copied first, so the instance can be changed while iterating.
+    pub fn iter_fields(&self) -> impl Iterator<Item = (String, LoxValue)> + use<> {
+        let fields: Vec<_> = self
+            .fields
+            .borrow()
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        fields.into_iter()
+    }
+
+    /// Returns the names of the fields, in the order they were first set.
+    pub fn field_names(&self) -> Vec<String> {
+        self.fields.borrow().keys().cloned().collect()
+    }
+
+    /// Makes every later [`Instance::set`] fail.
+    pub fn freeze(&self) {
+        self.frozen.set(true);
+    }
+
+    pub fn find_method(&self, name: &str) -> Option<Rc<Callable>> {
+        self.class.find_method(name)
     }
 
     pub fn class_name(&self) -> &str {
@@ -119,3 +372,148 @@ impl Display for Instance {
         write!(f, "instanceof({})", &self.class.name)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Class, Instance, LoxValue};
+    use crate::interpreter::ConversionError;
+    use crate::interpreter::callable::{Arity, Callable};
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    fn instance() -> LoxValue {
+        let class = Rc::new(Class::new(String::from("Foo"), HashMap::new(), None));
+        LoxValue::Instance(Rc::new(Instance::new(class)))
+    }
+
+    #[test]
+    fn mixed_type_keys() {
+        let string = LoxValue::String(Rc::new(String::from("key")));
+        let object = instance();
+
+        let mut map = HashMap::new();
+        map.insert(LoxValue::Number(1.0), "number");
+        map.insert(LoxValue::Boolean(true), "boolean");
+        map.insert(LoxValue::Nil, "nil");
+        map.insert(string, "string");
+        map.insert(object.clone(), "instance");
+
+        assert_eq!(map[&LoxValue::Number(1.0)], "number");
+        assert_eq!(map[&LoxValue::Boolean(true)], "boolean");
+        assert_eq!(map[&LoxValue::Nil], "nil");
+        assert_eq!(
+            map[&LoxValue::String(Rc::new(String::from("key")))],
+            "string"
+        );
+        assert_eq!(map[&object], "instance");
+
+        assert!(!map.contains_key(&LoxValue::Boolean(false)));
+        assert!(!map.contains_key(&LoxValue::String(Rc::new(String::from("1")))));
+        assert!(!map.contains_key(&instance()));
+    }
+
+    #[test]
+    fn special_numbers_are_consistent_keys() {
+        let mut map = HashMap::new();
+        map.insert(LoxValue::Number(f64::NAN), "nan");
+        map.insert(LoxValue::Number(0.0), "zero");
+
+        assert_eq!(map[&LoxValue::Number(-f64::NAN)], "nan");
+        assert_eq!(map[&LoxValue::Number(-0.0)], "zero");
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn fields_keep_insertion_order() {
+        let LoxValue::Instance(instance) = instance() else {
+            unreachable!()
+        };
+
+        for name in ["zeta", "alpha", "mid", "beta"] {
+            assert!(instance.set(name, LoxValue::Nil));
+        }
+        assert!(instance.set("alpha", LoxValue::Number(1.0)));
+
+        assert_eq!(instance.field_names(), ["zeta", "alpha", "mid", "beta"]);
+    }
+
+    #[test]
+    fn cached_method_lookup_matches_the_chain() {
+        let method = || {
+            Rc::new(Callable::Native {
+                arity: Arity::Fixed(0),
+                func: |_| Ok(LoxValue::Nil),
+            })
+        };
+        let class = |name: &str, methods: &[&str], super_class| {
+            let methods = methods.iter().map(|m| (m.to_string(), method())).collect();
+            Rc::new(Class::new(name.to_string(), methods, super_class))
+        };
+
+        let base = class("Base", &["greet", "shared"], None);
+        let middle = class("Middle", &["shared"], Some(base.clone()));
+        let leaf = class("Leaf", &[], Some(middle.clone()));
+
+        for _ in 0..2 {
+            for name in ["greet", "shared", "missing"] {
+                let (cached, uncached) = (leaf.find_method(name), leaf.lookup_method(name));
+                match (cached, uncached) {
+                    (Some(a), Some(b)) => assert!(Rc::ptr_eq(&a, &b)),
+                    (None, None) => {}
+                    _ => panic!("Cached and uncached lookups of {name} disagree"),
+                }
+            }
+        }
+
+        let shared = leaf.find_method("shared").unwrap();
+        assert!(Rc::ptr_eq(&shared, &middle.methods["shared"]));
+        assert!(!Rc::ptr_eq(&shared, &base.methods["shared"]));
+    }
+
+    #[test]
+    fn rust_conversions() {
+        assert_eq!(f64::try_from(LoxValue::from(2.5)), Ok(2.5));
+        assert_eq!(
+            String::try_from(LoxValue::from(String::from("owned"))),
+            Ok(String::from("owned"))
+        );
+        assert_eq!(
+            String::try_from(LoxValue::from("borrowed")),
+            Ok(String::from("borrowed"))
+        );
+        assert!(matches!(LoxValue::from(true), LoxValue::Boolean(true)));
+
+        assert_eq!(
+            f64::try_from(LoxValue::from("1")),
+            Err(ConversionError {
+                expected: "number",
+                got: "string"
+            })
+        );
+        let error = String::try_from(LoxValue::Nil).unwrap_err();
+        assert_eq!(error.to_string(), "Expected string, got nil");
+    }
+
+    #[test]
+    fn iterating_over_fields() {
+        let LoxValue::Instance(instance) = instance() else {
+            unreachable!()
+        };
+        assert!(instance.set("x", LoxValue::Number(1.0)));
+        assert!(instance.set("name", LoxValue::from("point")));
+
+        let mut fields = instance.iter_fields();
+        /* The fields were copied, so the instance is not borrowed while iterating */
+        assert!(instance.set("x", LoxValue::Number(2.0)));
+
+        assert_eq!(
+            fields.next(),
+            Some((String::from("x"), LoxValue::Number(1.0)))
+        );
+        assert_eq!(
+            fields.next(),
+            Some((String::from("name"), LoxValue::from("point")))
+        );
+        assert_eq!(fields.next(), None);
+    }
+}
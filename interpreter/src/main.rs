@@ -1,92 +1,297 @@
+mod diagnostic;
 mod interpreter;
 mod resolver;
 
-use resolver::Resolver;
+use diagnostic::{ColorChoice, Diagnostic, Severity};
+use resolver::{Resolver, ResolverError};
 
-use crate::interpreter::{Interpreter, InterpreterError};
-use std::io::{Cursor, Read, Result as IOResult};
+use crate::interpreter::{Interpreter, InterpreterError, PRELUDE};
+use std::io::{Cursor, Read, Result as IOResult, Write};
 use std::path::Path;
 use std::process::ExitCode;
 use std::sync::Mutex;
+use std::time::Instant;
+use syntax::Statement;
 
 static HAD_ERROR: Mutex<bool> = Mutex::new(false);
 static HAD_RUNTIME_ERROR: Mutex<bool> = Mutex::new(false);
+static EXIT_CODE: Mutex<Option<ExitCode>> = Mutex::new(None);
+static JSON_MESSAGES: Mutex<bool> = Mutex::new(false);
+static WARN_SHADOWING: Mutex<bool> = Mutex::new(false);
+static TIME_PHASES: Mutex<bool> = Mutex::new(false);
+static IMPLICIT_SEMICOLONS: Mutex<bool> = Mutex::new(false);
+static COLOR: Mutex<ColorChoice> = Mutex::new(ColorChoice::Auto);
 
 fn main() -> ExitCode {
     let args: Vec<String> = std::env::args().collect();
 
     if args.is_empty() {
-        println!("Usage: lox [script]");
+        println!(
+            "Usage: lox [--trace] [--ast-json] [--check] [--ieee-division] [--warn-precision] \
+             [--warn-shadowing] [--message-format=json] [--time] [--implicit-semicolons] \
+             [--color=auto|always|never] [--no-color] \
+             [-e <program> | --eval=<program> | script]"
+        );
         return ExitCode::FAILURE;
     }
 
-    let interpreter = Interpreter::new();
-    match args.get(1) {
-        Some(script) => run_file(script),
+    let mut script = None;
+    let mut trace = false;
+    let mut ast_json = false;
+    let mut check = false;
+    let mut ieee_division = false;
+    let mut warn_precision = false;
+    let mut program = None;
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trace" => trace = true,
+            "--ast-json" => ast_json = true,
+            "--check" => check = true,
+            "--ieee-division" => ieee_division = true,
+            "--warn-precision" => warn_precision = true,
+            "--warn-shadowing" => *WARN_SHADOWING.lock().unwrap() = true,
+            "--message-format=json" => *JSON_MESSAGES.lock().unwrap() = true,
+            "--time" => *TIME_PHASES.lock().unwrap() = true,
+            "--implicit-semicolons" => *IMPLICIT_SEMICOLONS.lock().unwrap() = true,
+            "--no-color" => *COLOR.lock().unwrap() = ColorChoice::Never,
+            _ if arg.starts_with("--color=") => {
+                match ColorChoice::parse(&arg["--color=".len()..]) {
+                    Some(choice) => *COLOR.lock().unwrap() = choice,
+                    None => {
+                        eprintln!("--color must be auto, always or never");
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+            "-e" => match args.next() {
+                Some(source) => program = Some(source.clone()),
+                None => {
+                    eprintln!("-e requires a program");
+                    return ExitCode::FAILURE;
+                }
+            },
+            _ if arg.starts_with("--eval=") => program = Some(arg["--eval=".len()..].to_string()),
+            _ => script = Some(arg),
+        }
+    }
+
+    /* An inline program takes the place of the script */
+    let source = program.or_else(|| script.map(read_file));
+
+    if ast_json {
+        match source {
+            Some(source) => print_ast(&source),
+            None => {
+                eprintln!("--ast-json requires a script");
+                return ExitCode::FAILURE;
+            }
+        }
+        return exit_code();
+    }
+
+    /* The prelude is loaded before the flags, so it is not traced nor warned about */
+    let mut interpreter = Interpreter::new();
+    if let Err(e) = interpreter.load_prelude(PRELUDE) {
+        eprintln!("{}", paint_stderr(Severity::Error, e));
+        return ExitCode::FAILURE;
+    }
+    interpreter.set_trace(trace);
+    interpreter.set_ieee_division(ieee_division);
+    interpreter.set_warn_precision(warn_precision);
+
+    if check {
+        match source {
+            Some(source) => {
+                prepare(&source, &interpreter);
+            }
+            None => {
+                eprintln!("--check requires a script");
+                return ExitCode::FAILURE;
+            }
+        }
+        return exit_code();
+    }
+
+    match source {
+        Some(source) => run(&source, &interpreter),
         None => run_prompt(&interpreter).unwrap(),
     }
 
-    if *HAD_ERROR.lock().unwrap() {
+    exit_code()
+}
+
+fn exit_code() -> ExitCode {
+    if let Some(code) = *EXIT_CODE.lock().unwrap() {
+        code
+    } else if *HAD_ERROR.lock().unwrap() {
         ExitCode::FAILURE
     } else {
         ExitCode::SUCCESS
     }
 }
 
-fn run(source: &str, interpreter: &Interpreter) {
-    let scanner = syntax::Scanner::new(Cursor::new(source));
+/// Scans and parses the source, reporting any errors found. Returns `None` if the source is
+/// not a valid program.
+fn parse(source: &str) -> Option<Vec<Statement>> {
+    let (tokens, had_syntax_error) = timed("scan", || {
+        let mut scanner = syntax::Scanner::new(Cursor::new(source));
+        scanner.set_line_recovery(true);
 
-    let tokens = match scanner.scan_tokens() {
-        Ok(tokens) => tokens,
-        Err(e) => {
-            eprintln!("Syntax Error: {e}");
-            return;
+        let mut tokens = Vec::new();
+        let mut had_syntax_error = false;
+        for token in scanner {
+            match token {
+                Ok(token) => tokens.push(token),
+                Err(e) => {
+                    if !emit_json(Diagnostic::from(&e)) {
+                        eprintln!(
+                            "{}",
+                            paint_stderr(Severity::Error, format!("Syntax Error: {e}"))
+                        );
+                    }
+                    had_syntax_error = true;
+                }
+            }
         }
-    };
+        (tokens, had_syntax_error)
+    });
+
+    if had_syntax_error {
+        *HAD_ERROR.lock().unwrap() = true;
+        return None;
+    }
 
     let mut parser = syntax::Parser::new(&tokens);
-    let statements = match parser.statements() {
-        Ok(stmts) => stmts,
+    parser.set_implicit_semicolons(*IMPLICIT_SEMICOLONS.lock().unwrap());
+    match timed("parse", || parser.statements().map_err(Box::new)) {
+        Ok(stmts) => Some(stmts),
         Err(e) => {
-            eprintln!("{e}");
-            return;
+            if !emit_json(Diagnostic::from(&*e)) {
+                eprintln!("{}", paint_stderr(Severity::Error, &e));
+            }
+            *HAD_ERROR.lock().unwrap() = true;
+            None
         }
-    };
+    }
+}
+
+/// Scans, parses and resolves the source, reporting any errors found. Returns the statements
+/// ready to be interpreted, or `None` if there were errors.
+fn prepare(source: &str, interpreter: &Interpreter) -> Option<Vec<Statement>> {
+    let mut statements = parse(source)?;
+    let fold_errors = timed("fold", || syntax::fold::fold_constants(&mut statements));
+    if !interpreter.ieee_division() && !fold_errors.is_empty() {
+        for error in &fold_errors {
+            if !emit_json(Diagnostic::from(error)) {
+                eprintln!("{}", paint_stderr(Severity::Error, error));
+            }
+        }
+        *HAD_ERROR.lock().unwrap() = true;
+        return None;
+    }
 
     let mut resolver = Resolver::new(interpreter);
+    resolver.set_warn_shadowing(*WARN_SHADOWING.lock().unwrap());
 
-    if let Err(e) = resolver.resolve_statements(&statements) {
-        static_error(&format!("{e}"));
+    let result = timed("resolve", || resolver.resolve_statements(&statements));
+    for warning in resolver.warnings() {
+        if !emit_json(Diagnostic::from(warning)) {
+            eprintln!(
+                "{}",
+                paint_stderr(Severity::Warning, format!("Warning: {warning}"))
+            );
+        }
     }
 
-    if let Err(e) = interpreter.interpret(&statements) {
-        runtime_error(e);
+    if let Err(e) = result {
+        static_error(&e);
+        return None;
     }
+
+    Some(statements)
 }
 
-fn run_file(path: impl AsRef<Path>) {
+fn run(source: &str, interpreter: &Interpreter) {
+    let Some(statements) = prepare(source, interpreter) else {
+        return;
+    };
+
+    match timed("interpret", || interpreter.interpret(&statements)) {
+        Ok(Some(code)) => *EXIT_CODE.lock().unwrap() = Some(code),
+        Ok(None) => {}
+        Err(e) => runtime_error(e),
+    }
+}
+
+/// Runs a phase of the interpreter, printing how long it took to the standard error when
+/// `--time` is given.
+fn timed<T>(phase: &str, f: impl FnOnce() -> T) -> T {
+    if !*TIME_PHASES.lock().unwrap() {
+        return f();
+    }
+
+    let start = Instant::now();
+    let result = f();
+    eprintln!("[time] {phase}: {:?}", start.elapsed());
+    result
+}
+
+/// Prints the syntax tree of the source as JSON, without running it.
+fn print_ast(source: &str) {
+    let Some(statements) = parse(source) else {
+        return;
+    };
+
+    match serde_json::to_string_pretty(&statements) {
+        Ok(json) => println!("{json}"),
+        Err(e) => {
+            eprintln!("Could not serialize the syntax tree: {e}");
+            *HAD_ERROR.lock().unwrap() = true;
+        }
+    }
+}
+
+fn read_file(path: impl AsRef<Path>) -> String {
     let mut file = std::fs::File::open(path).unwrap();
     let mut contents = String::new();
 
-    let interpreter = Interpreter::new();
-
     file.read_to_string(&mut contents).unwrap();
-    run(&contents, &interpreter);
+    contents
 }
 
 fn run_prompt(interpreter: &Interpreter) -> IOResult<()> {
     let reader = std::io::stdin();
+    let mut stdout = std::io::stdout();
 
     loop {
-        print!(">");
         let mut line = String::new();
-        reader.read_line(&mut line)?;
+        loop {
+            /* Input with unclosed delimiters continues on the next line, under a "..." prompt */
+            print!("{}", if line.is_empty() { ">" } else { "..." });
+            stdout.flush()?;
+
+            if reader.read_line(&mut line)? == 0 || !needs_continuation(&line) {
+                break;
+            }
+        }
 
         if line.is_empty() {
             break;
         }
 
+        if line.trim_start().starts_with(':') {
+            if !run_command(interpreter, line.trim()) {
+                break;
+            }
+            continue;
+        }
+
         run(&line, interpreter);
+        if EXIT_CODE.lock().unwrap().is_some() {
+            break;
+        }
+        interpreter.forget_undefined_globals();
 
         *HAD_ERROR.lock().unwrap() = false;
         *HAD_RUNTIME_ERROR.lock().unwrap() = false;
@@ -95,21 +300,115 @@ fn run_prompt(interpreter: &Interpreter) -> IOResult<()> {
     Ok(())
 }
 
-fn error(line: usize, message: &str) {
-    report(line, "", message);
+/// Returns whether the REPL input is incomplete because it has unclosed parentheses, braces,
+/// strings or characters, in which case the next line is read as part of it.
+fn needs_continuation(source: &str) -> bool {
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '/') if chars.peek() == Some(&'/') => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            (None, '(' | '{') => depth += 1,
+            (None, ')' | '}') => depth -= 1,
+            (None, _) => {}
+        }
+    }
+
+    depth > 0 || quote.is_some()
+}
+
+const REPL_HELP: &str = "\
+:help          Show this message
+:vars          List the globals defined in this session
+:clear [name]  Remove a global, or every global if no name is given
+:try <code>    Run the code, and then undo the globals it defined or assigned
+:exit          Leave the REPL";
+
+/// Runs a REPL command, which is a line starting with `:` instead of Lox source. Returns
+/// whether the REPL should keep reading lines.
+fn run_command(interpreter: &Interpreter, command: &str) -> bool {
+    let (name, argument) = command.split_once(' ').unwrap_or((command, ""));
+
+    match name {
+        ":help" => println!("{REPL_HELP}"),
+        ":vars" => {
+            for name in interpreter.global_names() {
+                println!("{name}");
+            }
+        }
+        ":clear" => clear(interpreter, argument.trim()),
+        ":try" => interpreter.speculatively(|| run(argument, interpreter)),
+        ":exit" => return false,
+        _ => println!("Unknown command {name}, use :help to list the commands"),
+    }
+
+    true
 }
 
-fn static_error(error: &str) {
+/// Handles the `:clear` REPL command, which removes a single global when given a name and
+/// every global but the natives and the prelude otherwise.
+fn clear(interpreter: &Interpreter, name: &str) {
+    if name.is_empty() {
+        interpreter.reset_globals();
+        if let Err(e) = interpreter.load_prelude(PRELUDE) {
+            eprintln!("{}", paint_stderr(Severity::Error, e));
+        }
+    } else if !interpreter.undefine_global(name) {
+        println!("{name} is not defined");
+    }
+}
+
+fn static_error(error: &ResolverError) {
     *HAD_ERROR.lock().unwrap() = true;
-    println!("Resolver error: {error}");
+    if !emit_json(Diagnostic::from(error)) {
+        println!("{}", paint_stdout(format!("Resolver error: {error}")));
+    }
 }
 
 fn runtime_error(error: impl AsRef<InterpreterError> + std::fmt::Display) {
-    println!("{error}");
+    if !emit_json(Diagnostic::from(error.as_ref())) {
+        println!("{}", paint_stdout(&error));
+    }
     *HAD_RUNTIME_ERROR.lock().unwrap() = true;
 }
 
-fn report(line: usize, s_where: &str, message: &str) {
-    println!("[line {line}] Error {s_where}: {message}");
-    *HAD_ERROR.lock().unwrap() = true;
+/// Colors a diagnostic that is printed to the standard error, following `--color`.
+fn paint_stderr(severity: Severity, text: impl std::fmt::Display) -> String {
+    COLOR
+        .lock()
+        .unwrap()
+        .paint(severity, text, std::io::stderr())
+}
+
+/// Colors an error that is printed to the standard output, following `--color`.
+fn paint_stdout(text: impl std::fmt::Display) -> String {
+    COLOR
+        .lock()
+        .unwrap()
+        .paint(Severity::Error, text, std::io::stdout())
+}
+
+/// Prints the diagnostic to the standard error as a single line of JSON when
+/// `--message-format=json` was given. Returns whether it was printed, so that the caller falls
+/// back to the human-readable format otherwise.
+fn emit_json(diagnostic: Diagnostic) -> bool {
+    if !*JSON_MESSAGES.lock().unwrap() {
+        return false;
+    }
+
+    match serde_json::to_string(&diagnostic) {
+        Ok(json) => eprintln!("{json}"),
+        Err(e) => eprintln!("Could not serialize the diagnostic: {e}"),
+    }
+    true
 }
@@ -1,27 +1,145 @@
 pub(crate) use crate::interpreter::Interpreter;
 use std::collections::HashMap;
-use syntax::{Expression, Statement};
+use std::fmt::{Display, Formatter};
+use syntax::statement::Function;
+use syntax::{Expression, Statement, Token};
 
 #[derive(thiserror::Error, Debug)]
 pub enum ResolverError {
     #[error("Variable {0} cannot be read before it is initialized")]
     NotInitialized(String),
-    #[error("Variable {0} is already declared in the current scope")]
-    VariableAlreadyExists(String),
-    #[error("Return statement has been used outside function")]
-    ReturnNotInFunction,
+    #[error("Variable {0} in line {1} is already declared in the current scope")]
+    VariableAlreadyExists(String, usize),
+    #[error("Return statement in line {0} has been used outside function")]
+    ReturnNotInFunction(usize),
     #[error("Invalid use of the this keyword in line {0}")]
     InvalidThis(usize),
+    /// `super` can only be used to read a method, as in `super.method`.
+    #[error("Invalid use of the super keyword in line {0}")]
+    InvalidSuper(usize),
+    #[error("Cannot use super in line {0}, in a class without a superclass")]
+    SuperWithoutSuperclass(usize),
+    #[error("Cannot use this in line {0}, inside a static method")]
+    ThisInStaticMethod(usize),
     #[error("Invalid use of return in an Initializer in line {0}")]
     InvalidInitReturn(usize),
-    #[error("Class {0} must not inherit itself")]
-    SelfInheritance(String),
+    #[error("Class {0} in line {1} must not inherit itself")]
+    SelfInheritance(String, usize),
+    #[error("Return statement in line {0} cannot be used inside a block expression")]
+    ReturnInBlockExpression(usize),
+    #[error("The {0} statement in line {1} cannot be used to leave a block expression")]
+    LoopControlInBlockExpression(String, usize),
+}
+
+impl ResolverError {
+    /// Returns the line the error was found in, if the error knows it.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ResolverError::InvalidThis(line)
+            | ResolverError::InvalidSuper(line)
+            | ResolverError::SuperWithoutSuperclass(line)
+            | ResolverError::ThisInStaticMethod(line)
+            | ResolverError::InvalidInitReturn(line)
+            | ResolverError::ReturnInBlockExpression(line)
+            | ResolverError::ReturnNotInFunction(line)
+            | ResolverError::VariableAlreadyExists(_, line)
+            | ResolverError::LoopControlInBlockExpression(_, line)
+            | ResolverError::SelfInheritance(_, line) => Some(*line),
+            ResolverError::NotInitialized(_) => None,
+        }
+    }
+}
+
+/// A declaration that hides a variable with the same name from an enclosing scope. Shadowing is
+/// legal, so this is only reported when asked for with [`Resolver::set_warn_shadowing`].
+#[derive(Debug, PartialEq)]
+pub struct ShadowWarning {
+    pub name: String,
+    pub line: usize,
+    pub outer_line: Option<usize>,
+}
+
+impl Display for ShadowWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Variable {} declared in line {} shadows the one",
+            self.name, self.line
+        )?;
+        match self.outer_line {
+            Some(line) => write!(f, " declared in line {line}"),
+            None => write!(f, " of an enclosing scope"),
+        }
+    }
+}
+
+/// A statement that can never run, because it follows a `return`, `break`, `continue` or
+/// `throw` in the same block.
+#[derive(Debug, PartialEq)]
+pub struct UnreachableWarning {
+    pub line: usize,
+    /// The keyword of the statement that leaves the block, and the line it is in.
+    pub keyword: String,
+    pub keyword_line: usize,
+}
+
+impl Display for UnreachableWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Unreachable code in line {} after the {} in line {}",
+            self.line, self.keyword, self.keyword_line
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ResolverWarning {
+    Shadow(ShadowWarning),
+    Unreachable(UnreachableWarning),
+}
+
+impl ResolverWarning {
+    /// Returns the line the warning was found in.
+    pub fn line(&self) -> usize {
+        match self {
+            ResolverWarning::Shadow(warning) => warning.line,
+            ResolverWarning::Unreachable(warning) => warning.line,
+        }
+    }
+}
+
+impl Display for ResolverWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolverWarning::Shadow(warning) => Display::fmt(warning, f),
+            ResolverWarning::Unreachable(warning) => Display::fmt(warning, f),
+        }
+    }
+}
+
+/// Returns the keyword of a statement that always leaves its block.
+fn block_exit(statement: &Statement) -> Option<&Token> {
+    match statement {
+        Statement::Return { keyword, .. }
+        | Statement::Break { keyword }
+        | Statement::Continue { keyword }
+        | Statement::Throw { keyword, .. } => Some(keyword),
+        _ => None,
+    }
+}
+
+/// A name declared in a local scope.
+struct Binding {
+    defined: bool,
+    line: Option<usize>,
 }
 
 enum FunctionType {
     None,
     Function,
     Method,
+    StaticMethod,
     Initializer,
 }
 
@@ -29,13 +147,21 @@ enum FunctionType {
 enum ClassType {
     None,
     Class,
+    Subclass,
 }
 
 pub struct Resolver<'i> {
     interpreter: &'i Interpreter,
-    scopes: Vec<HashMap<String, bool>>,
+    scopes: Vec<HashMap<String, Binding>>,
     function_type: FunctionType,
     class_type: ClassType,
+    in_block_expression: bool,
+    /// Whether break and continue would apply to a loop, rather than leave a block expression.
+    in_loop: bool,
+    /// A global whose initializer is being resolved, and which did not exist before it.
+    initializing_global: Option<String>,
+    warn_shadowing: bool,
+    warnings: Vec<ResolverWarning>,
 }
 
 impl<'i> Resolver<'i> {
@@ -45,9 +171,24 @@ impl<'i> Resolver<'i> {
             scopes: Vec::new(),
             function_type: FunctionType::None,
             class_type: ClassType::None,
+            in_block_expression: false,
+            in_loop: false,
+            initializing_global: None,
+            warn_shadowing: false,
+            warnings: Vec::new(),
         }
     }
 
+    /// When enabled, declarations that shadow a variable of an enclosing scope are collected as
+    /// [`warnings`](Self::warnings).
+    pub fn set_warn_shadowing(&mut self, warn_shadowing: bool) {
+        self.warn_shadowing = warn_shadowing;
+    }
+
+    pub fn warnings(&self) -> &[ResolverWarning] {
+        &self.warnings
+    }
+
     fn begin_scope(&mut self) {
         self.scopes.push(HashMap::new());
     }
@@ -57,7 +198,35 @@ impl<'i> Resolver<'i> {
     }
 
     pub fn resolve_statements(&mut self, statements: &[Statement]) -> Result<(), ResolverError> {
+        /* Functions are declared before anything else in their scope, so code above them, like
+         * mutually recursive functions, can already refer to them. Classes are not, the same as
+         * in the interpreter */
+        for statement in statements {
+            if let Statement::FunctionDeclaration(Function { name, .. }) = statement {
+                self.declare(name, statement.line())?;
+                self.define(name);
+            }
+        }
+
+        /* Only the first unreachable statement of a block is reported. Function declarations
+         * are hoisted, so they are reachable wherever they are */
+        let mut exit = None;
+        let mut reported = false;
         for statement in statements {
+            match exit {
+                None => exit = block_exit(statement),
+                Some(_) if reported || matches!(statement, Statement::FunctionDeclaration(_)) => {}
+                Some(keyword) => {
+                    self.warnings
+                        .push(ResolverWarning::Unreachable(UnreachableWarning {
+                            line: statement.line(),
+                            keyword: keyword.lexeme().to_string(),
+                            keyword_line: keyword.line(),
+                        }));
+                    reported = true;
+                }
+            }
+
             self.resolve_statement(statement)?;
         }
 
@@ -66,18 +235,29 @@ impl<'i> Resolver<'i> {
 
     fn resolve_statement(&mut self, statement: &Statement) -> Result<(), ResolverError> {
         match statement {
-            Statement::Block(block) => {
+            Statement::Block { statements, .. } => {
                 self.begin_scope();
-                self.resolve_statements(block)?;
+                self.resolve_statements(statements)?;
                 self.end_scope();
                 Ok(())
             }
 
-            Statement::VariableDeclaration { name, initializer } => {
-                self.declare(name)?;
+            Statement::VariableDeclaration {
+                name, initializer, ..
+            } => {
+                /* Globals may be redeclared, and then the initializer reads the earlier one */
+                let new_global = self.scopes.is_empty()
+                    && self.interpreter.global_declaration(name).is_none()
+                    && !self.interpreter.is_global(name);
+                self.declare(name, statement.line())?;
 
                 if let Some(initializer) = initializer {
-                    self.resolve_expression(initializer)?;
+                    if new_global {
+                        self.initializing_global = Some(name.clone());
+                    }
+                    let result = self.resolve_expression(initializer);
+                    self.initializing_global = None;
+                    result?;
                 }
 
                 self.define(name);
@@ -86,55 +266,97 @@ impl<'i> Resolver<'i> {
             Statement::ClassDeclaration {
                 name,
                 methods,
+                static_fields,
+                static_methods,
                 super_class,
+                line,
             } => {
-                self.declare(name)?;
-                self.define(name);
-
-                if let Some(Expression::Var(super_class)) = super_class {
-                    if super_class.token.lexeme() == name {
-                        return Err(ResolverError::SelfInheritance(name.to_string()));
-                    }
+                if let Some(Expression::Var(super_class, _)) = super_class
+                    && super_class.token.lexeme() == name
+                {
+                    return Err(ResolverError::SelfInheritance(name.to_string(), *line));
                 }
 
+                self.declare(name, *line)?;
+                self.define(name);
+
                 if let Some(super_class) = super_class {
                     self.resolve_expression(super_class)?;
                 }
 
+                /* Static fields are initialized when the class is declared, outside of any method */
+                for initializer in static_fields.iter().flat_map(|f| &f.initializer) {
+                    self.resolve_expression(initializer)?;
+                }
+
+                /* Static methods are not bound to an instance, so they see neither this nor super */
+                for method in static_methods {
+                    self.resolve_function(
+                        &method.parameters,
+                        &method.body,
+                        FunctionType::StaticMethod,
+                    )?;
+                }
+
+                /* Methods of a subclass see `super` in a scope around the one of `this` */
+                if super_class.is_some() {
+                    self.begin_scope();
+                    if let Some(scope) = self.scopes.last_mut() {
+                        let binding = Binding {
+                            defined: true,
+                            line: None,
+                        };
+                        scope.insert(String::from("super"), binding);
+                    }
+                }
+
                 let current_class = self.class_type;
-                self.class_type = ClassType::Class;
+                self.class_type = match super_class {
+                    Some(_) => ClassType::Subclass,
+                    None => ClassType::Class,
+                };
                 self.begin_scope();
 
                 if let Some(scope) = self.scopes.last_mut() {
-                    scope.insert(String::from("this"), true);
+                    let this = Binding {
+                        defined: true,
+                        line: None,
+                    };
+                    scope.insert(String::from("this"), this);
                 }
 
                 for method in methods {
-                    self.function_type = if method.name == "init" {
+                    let function_type = if method.name == "init" {
                         FunctionType::Initializer
                     } else {
                         FunctionType::Method
                     };
-                    self.resolve_function(&method.parameters, &method.body)?;
+                    self.resolve_function(&method.parameters, &method.body, function_type)?;
                 }
 
                 self.end_scope();
                 self.class_type = current_class;
+                if super_class.is_some() {
+                    self.end_scope();
+                }
 
                 Ok(())
             }
             Statement::Expression(expression) => self.resolve_expression(expression),
-            Statement::Print(expression) => self.resolve_expression(expression),
+            Statement::Print(expressions) => {
+                for expression in expressions {
+                    self.resolve_expression(expression)?;
+                }
+                Ok(())
+            }
             Statement::FunctionDeclaration(function) => {
-                self.declare(&function.name)?;
-                self.define(&function.name);
-
-                self.resolve_function(&function.parameters, &function.body)
+                self.resolve_function(&function.parameters, &function.body, FunctionType::Function)
             }
             Statement::If {
                 condition,
                 then_branch,
                 else_branch,
+                ..
             } => {
                 self.resolve_expression(condition)?;
                 self.resolve_statement(then_branch)?;
@@ -145,22 +367,67 @@ impl<'i> Resolver<'i> {
 
                 Ok(())
             }
-            Statement::While { condition, body } => self
-                .resolve_expression(condition)
-                .and(self.resolve_statement(body)),
-            Statement::For { .. } => todo!(),
+            Statement::While {
+                condition,
+                body,
+                else_branch,
+                ..
+            } => {
+                self.resolve_expression(condition)?;
+                self.resolve_loop_body(body)?;
+                if let Some(else_branch) = else_branch {
+                    self.resolve_statement(else_branch)?;
+                }
+                Ok(())
+            }
+            Statement::For {
+                initializer,
+                condition,
+                increment,
+                body,
+                else_branch,
+                ..
+            } => {
+                self.begin_scope();
+
+                if let Some(initializer) = initializer {
+                    self.resolve_statement(initializer)?;
+                }
+                if let Some(condition) = condition {
+                    self.resolve_expression(condition)?;
+                }
+                if let Some(increment) = increment {
+                    self.resolve_expression(increment)?;
+                }
+                self.resolve_loop_body(body)?;
+
+                self.end_scope();
+
+                /* The else branch runs after the loop, where its variables are gone */
+                if let Some(else_branch) = else_branch {
+                    self.resolve_statement(else_branch)?;
+                }
+                Ok(())
+            }
+            Statement::Return { keyword, .. } if self.in_block_expression => {
+                Err(ResolverError::ReturnInBlockExpression(keyword.line()))
+            }
             Statement::Return {
                 keyword,
                 expression,
             } => match (&self.function_type, expression) {
                 /* Invalid return statement outside of a function */
-                (FunctionType::None, _) => Err(ResolverError::ReturnNotInFunction),
+                (FunctionType::None, _) => Err(ResolverError::ReturnNotInFunction(keyword.line())),
 
                 /* Resolve expression following the statement */
-                (FunctionType::Method | FunctionType::Function, Some(expression)) => {
-                    self.resolve_expression(expression)
-                }
-                (FunctionType::Method | FunctionType::Function, None) => Ok(()),
+                (
+                    FunctionType::Method | FunctionType::StaticMethod | FunctionType::Function,
+                    Some(expression),
+                ) => self.resolve_expression(expression),
+                (
+                    FunctionType::Method | FunctionType::StaticMethod | FunctionType::Function,
+                    None,
+                ) => Ok(()),
 
                 /* Early return in an initializer */
                 (FunctionType::Initializer, None) => Ok(()),
@@ -170,19 +437,79 @@ impl<'i> Resolver<'i> {
                     Err(ResolverError::InvalidInitReturn(keyword.line()))
                 }
             },
+            Statement::Throw { expression, .. } => self.resolve_expression(expression),
+            Statement::Match { value, arms, .. } => {
+                self.resolve_expression(value)?;
+                for arm in arms {
+                    self.resolve_statement(&arm.body)?;
+                }
+                Ok(())
+            }
+            Statement::With { resource, body, .. } => {
+                self.resolve_expression(resource)?;
+
+                self.begin_scope();
+                self.resolve_statements(body)?;
+                self.end_scope();
+                Ok(())
+            }
+            Statement::Try {
+                body,
+                catch_name,
+                catch_body,
+                finally_body,
+                ..
+            } => {
+                self.begin_scope();
+                self.resolve_statements(body)?;
+                self.end_scope();
+
+                self.begin_scope();
+                self.declare(catch_name.lexeme(), catch_name.line())?;
+                self.define(catch_name.lexeme());
+                self.resolve_statements(catch_body)?;
+                self.end_scope();
+
+                if let Some(finally_body) = finally_body {
+                    self.begin_scope();
+                    self.resolve_statements(finally_body)?;
+                    self.end_scope();
+                }
+                Ok(())
+            }
+            Statement::Break { keyword } | Statement::Continue { keyword }
+                if self.in_block_expression && !self.in_loop =>
+            {
+                Err(ResolverError::LoopControlInBlockExpression(
+                    keyword.lexeme().to_string(),
+                    keyword.line(),
+                ))
+            }
             // TODO: Add support for checking that this is inside a loop
             Statement::Break { .. } => Ok(()),
             Statement::Continue { .. } => Ok(()),
         }
     }
 
-    fn resolve_expression(&mut self, expr: &Expression) -> Result<(), ResolverError> {
+    fn resolve_loop_body(&mut self, body: &Statement) -> Result<(), ResolverError> {
+        let in_loop = std::mem::replace(&mut self.in_loop, true);
+        let result = self.resolve_statement(body);
+        self.in_loop = in_loop;
+        result
+    }
+
+    pub fn resolve_expression(&mut self, expr: &Expression) -> Result<(), ResolverError> {
         match expr {
-            Expression::Var(variable) => {
+            Expression::Var(variable, _) => {
                 let name = variable.token.lexeme();
 
                 match self.scopes.last() {
-                    Some(scope) if matches!(scope.get(name), Some(false)) => {
+                    Some(scope)
+                        if matches!(scope.get(name), Some(Binding { defined: false, .. })) =>
+                    {
+                        return Err(ResolverError::NotInitialized(String::from(name)));
+                    }
+                    None if self.initializing_global.as_deref() == Some(name) => {
                         return Err(ResolverError::NotInitialized(String::from(name)));
                     }
                     Some(_) | None => self.resolve_local(expr, name),
@@ -190,25 +517,69 @@ impl<'i> Resolver<'i> {
 
                 Ok(())
             }
-            Expression::This { keyword } => {
-                if !matches!(self.class_type, ClassType::Class) {
+            Expression::This { keyword, .. } => {
+                if matches!(self.function_type, FunctionType::StaticMethod) {
+                    return Err(ResolverError::ThisInStaticMethod(keyword.line()));
+                }
+                if matches!(self.class_type, ClassType::None) {
                     return Err(ResolverError::InvalidThis(keyword.line()));
                 }
                 self.resolve_local(expr, keyword.lexeme());
                 Ok(())
             }
-            Expression::Super { keyword } => {
-                todo!()
-            }
+            Expression::Super { keyword, .. } => Err(ResolverError::InvalidSuper(keyword.line())),
             Expression::Binary { left, right, .. } => self
                 .resolve_expression(left)
                 .and(self.resolve_expression(right)),
-            Expression::Grouping(expression) => self.resolve_expression(expression),
-            Expression::Unary(_, expression) => self.resolve_expression(expression),
+            Expression::Ternary {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => {
+                self.resolve_expression(condition)?;
+                self.resolve_expression(then_expr)?;
+                self.resolve_expression(else_expr)
+            }
+            Expression::Block(statements, value, _) => {
+                let in_block_expression = self.in_block_expression;
+                let in_loop = self.in_loop;
+                self.in_block_expression = true;
+                self.in_loop = false;
+                self.begin_scope();
+
+                self.resolve_statements(statements)?;
+                self.resolve_expression(value)?;
+
+                self.end_scope();
+                self.in_block_expression = in_block_expression;
+                self.in_loop = in_loop;
+                Ok(())
+            }
+            Expression::Comma(expressions, _) => {
+                for expression in expressions {
+                    self.resolve_expression(expression)?;
+                }
+                Ok(())
+            }
+            Expression::Lambda {
+                parameters, body, ..
+            } => self.resolve_function(parameters, body, FunctionType::Function),
+            Expression::Grouping(expression, _) => self.resolve_expression(expression),
+            Expression::Unary(_, expression, _) => self.resolve_expression(expression),
+            Expression::Prefix { target, .. } | Expression::Postfix { target, .. } => {
+                if let Expression::Get { expression, .. } = &**target
+                    && let Expression::Super { keyword, .. } = &**expression
+                {
+                    return Err(ResolverError::InvalidSuper(keyword.line()));
+                }
+                self.resolve_expression(target)
+            }
             Expression::Assignment {
                 name,
                 value,
                 token: _,
+                ..
             } => {
                 self.resolve_expression(value)?;
                 self.resolve_local(expr, name);
@@ -216,7 +587,7 @@ impl<'i> Resolver<'i> {
                 Ok(())
             }
             // Logical Expressions
-            Expression::Or { left, right } | Expression::And { left, right } => self
+            Expression::Or { left, right, .. } | Expression::And { left, right, .. } => self
                 .resolve_expression(left)
                 .and(self.resolve_expression(right)),
             Expression::Call { callee, args, .. } => {
@@ -228,15 +599,39 @@ impl<'i> Resolver<'i> {
 
                 Ok(())
             }
-            Expression::Get { expression, .. } => self.resolve_expression(expression),
+            Expression::Get { expression, .. } => match &**expression {
+                Expression::Super { keyword, .. } => match self.class_type {
+                    ClassType::Subclass
+                        if !matches!(self.function_type, FunctionType::StaticMethod) =>
+                    {
+                        self.resolve_local(expression, "super");
+                        Ok(())
+                    }
+                    ClassType::Class => Err(ResolverError::SuperWithoutSuperclass(keyword.line())),
+                    _ => Err(ResolverError::InvalidSuper(keyword.line())),
+                },
+                expression => self.resolve_expression(expression),
+            },
+            Expression::LogicalSet { target, value, .. } => {
+                if let Expression::Get { expression, .. } = &**target
+                    && let Expression::Super { keyword, .. } = &**expression
+                {
+                    return Err(ResolverError::InvalidSuper(keyword.line()));
+                }
+                self.resolve_expression(target)?;
+                self.resolve_expression(value)
+            }
+            Expression::Index { object, index, .. } => self
+                .resolve_expression(object)
+                .and(self.resolve_expression(index)),
             Expression::Set { object, value, .. } => self
                 .resolve_expression(object)
                 .and(self.resolve_expression(value)),
-            Expression::True
-            | Expression::False
-            | Expression::Number(_)
-            | Expression::String(_)
-            | Expression::Nil => Ok(()),
+            Expression::True(_)
+            | Expression::False(_)
+            | Expression::Number(..)
+            | Expression::String(_, _)
+            | Expression::Nil(_) => Ok(()),
         }
     }
 
@@ -244,19 +639,25 @@ impl<'i> Resolver<'i> {
         &mut self,
         parameters: &[syntax::Token],
         body: &[Statement],
+        function_type: FunctionType,
     ) -> Result<(), ResolverError> {
-        self.function_type = FunctionType::Function;
+        let enclosing_function = std::mem::replace(&mut self.function_type, function_type);
+        /* Functions declared inside block expressions may return normally */
+        let in_block_expression = std::mem::replace(&mut self.in_block_expression, false);
+        let in_loop = std::mem::replace(&mut self.in_loop, false);
         self.begin_scope();
 
         for param in parameters {
-            self.declare(param.lexeme())?;
+            self.declare(param.lexeme(), param.line())?;
             self.define(param.lexeme());
         }
 
         self.resolve_statements(body)?;
 
         self.end_scope();
-        self.function_type = FunctionType::None;
+        self.function_type = enclosing_function;
+        self.in_block_expression = in_block_expression;
+        self.in_loop = in_loop;
 
         Ok(())
     }
@@ -276,21 +677,235 @@ impl<'i> Resolver<'i> {
             None => return,
         };
 
-        scope.insert(String::from(name), true);
+        scope
+            .entry(String::from(name))
+            .or_insert(Binding {
+                defined: false,
+                line: None,
+            })
+            .defined = true;
     }
 
-    fn declare(&mut self, name: &str) -> Result<(), ResolverError> {
-        let scope = match self.scopes.last_mut() {
-            Some(scope) => scope,
-            None => return Ok(()),
+    fn declare(&mut self, name: &str, line: usize) -> Result<(), ResolverError> {
+        let Some((scope, enclosing)) = self.scopes.split_last_mut() else {
+            self.interpreter.declare_global(name, Some(line));
+            return Ok(());
         };
 
         if scope.contains_key(name) {
-            return Err(ResolverError::VariableAlreadyExists(String::from(name)));
+            return Err(ResolverError::VariableAlreadyExists(
+                String::from(name),
+                line,
+            ));
+        }
+
+        if self.warn_shadowing {
+            let outer = enclosing
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name).map(|binding| binding.line))
+                .or_else(|| self.interpreter.global_declaration(name));
+
+            if let Some(outer_line) = outer {
+                self.warnings.push(ResolverWarning::Shadow(ShadowWarning {
+                    name: String::from(name),
+                    line,
+                    outer_line,
+                }));
+            }
         }
 
-        scope.insert(String::from(name), false);
+        let binding = Binding {
+            defined: false,
+            line: Some(line),
+        };
+        scope.insert(String::from(name), binding);
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Resolver, ResolverError, ResolverWarning, ShadowWarning, UnreachableWarning};
+    use crate::interpreter::Interpreter;
+    use std::io::Cursor;
+
+    fn resolve(source: &str) -> Result<(), ResolverError> {
+        let tokens = syntax::Scanner::new(Cursor::new(source))
+            .scan_tokens()
+            .unwrap();
+        let statements = syntax::Parser::new(&tokens).statements().unwrap();
+        let interpreter = Interpreter::new();
+        Resolver::new(&interpreter).resolve_statements(&statements)
+    }
+
+    #[test]
+    fn return_in_nested_function() {
+        assert!(resolve("fun f() { fun g() { return 1; } return g(); }").is_ok());
+    }
+
+    #[test]
+    fn initializer_cannot_return_value() {
+        assert!(matches!(
+            resolve("class A { init() { return 1; } }"),
+            Err(ResolverError::InvalidInitReturn(1))
+        ));
+        assert!(resolve("class A { init() { return; } }").is_ok());
+    }
+
+    #[test]
+    fn return_inside_block_expression() {
+        assert!(matches!(
+            resolve("fun f() { var x = { return 1; 2 }; }"),
+            Err(ResolverError::ReturnInBlockExpression(1))
+        ));
+        assert!(resolve("var x = { fun g() { return 1; } g() };").is_ok());
+    }
+
+    #[test]
+    fn loop_control_inside_block_expression() {
+        assert!(matches!(
+            resolve("while (true) { var x = { break; 1 }; }"),
+            Err(ResolverError::LoopControlInBlockExpression(keyword, 1)) if keyword == "break"
+        ));
+        assert!(matches!(
+            resolve("for (;;) { print { continue; 1 }; }"),
+            Err(ResolverError::LoopControlInBlockExpression(keyword, 1)) if keyword == "continue"
+        ));
+        assert!(resolve("var x = { while (true) { break; } 1 };").is_ok());
+    }
+
+    #[test]
+    fn shadowing_warnings() {
+        let source =
+            "var x = 1;\nfun f(y) {\n  var x = 2;\n  {\n    var y = 3;\n    var z;\n  }\n}";
+        let tokens = syntax::Scanner::new(Cursor::new(source))
+            .scan_tokens()
+            .unwrap();
+        let statements = syntax::Parser::new(&tokens).statements().unwrap();
+        let interpreter = Interpreter::new();
+
+        let mut resolver = Resolver::new(&interpreter);
+        resolver.set_warn_shadowing(true);
+        resolver.resolve_statements(&statements).unwrap();
+
+        assert_eq!(
+            resolver.warnings(),
+            [
+                ResolverWarning::Shadow(ShadowWarning {
+                    name: String::from("x"),
+                    line: 3,
+                    outer_line: Some(1),
+                }),
+                ResolverWarning::Shadow(ShadowWarning {
+                    name: String::from("y"),
+                    line: 5,
+                    outer_line: Some(2),
+                }),
+            ]
+        );
+        assert_eq!(
+            resolver.warnings()[0].to_string(),
+            "Variable x declared in line 3 shadows the one declared in line 1"
+        );
+
+        let mut resolver = Resolver::new(&interpreter);
+        resolver.resolve_statements(&statements).unwrap();
+        assert!(resolver.warnings().is_empty());
+    }
+
+    #[test]
+    fn unreachable_code_warnings() {
+        let source = "fun f() {\n  return 1;\n  print 2;\n  print 3;\n  fun g() {}\n}\n\
+                      while (true) {\n  fun h() {}\n  break;\n  fun i() {}\n}\n\
+                      fun j() {\n  if (true) return;\n  print 4;\n}";
+        let tokens = syntax::Scanner::new(Cursor::new(source))
+            .scan_tokens()
+            .unwrap();
+        let statements = syntax::Parser::new(&tokens).statements().unwrap();
+        let interpreter = Interpreter::new();
+
+        let mut resolver = Resolver::new(&interpreter);
+        resolver.resolve_statements(&statements).unwrap();
+
+        assert_eq!(
+            resolver.warnings(),
+            [ResolverWarning::Unreachable(UnreachableWarning {
+                line: 3,
+                keyword: String::from("return"),
+                keyword_line: 2,
+            })]
+        );
+        assert_eq!(
+            resolver.warnings()[0].to_string(),
+            "Unreachable code in line 3 after the return in line 2"
+        );
+    }
+
+    #[test]
+    fn super_must_access_a_method() {
+        assert!(resolve("class A {}\nclass B < A { m() { return super.m; } }").is_ok());
+        assert!(matches!(
+            resolve("class A {}\nclass B < A { m() { return super; } }"),
+            Err(ResolverError::InvalidSuper(2))
+        ));
+        assert!(matches!(
+            resolve("class A {}\nclass B < A { m() { super.count++; } }"),
+            Err(ResolverError::InvalidSuper(2))
+        ));
+    }
+
+    #[test]
+    fn this_and_super_in_the_wrong_class_context() {
+        assert!(matches!(
+            resolve("class A {\n  static make() { return this; }\n}"),
+            Err(ResolverError::ThisInStaticMethod(2))
+        ));
+        assert!(matches!(
+            resolve("class A {\n  m() { return super.m(); }\n}"),
+            Err(ResolverError::SuperWithoutSuperclass(2))
+        ));
+        assert!(matches!(
+            resolve("fun f() {\n  return super.m();\n}"),
+            Err(ResolverError::InvalidSuper(2))
+        ));
+        assert!(resolve("class A {}\nclass B < A {\n  m() { return super.m; }\n}").is_ok());
+    }
+
+    #[test]
+    fn statement_errors_report_their_line() {
+        let error = resolve("var a = 1;\n{\n  var b = 1;\n  var b = 2;\n}").unwrap_err();
+        assert!(matches!(
+            &error,
+            ResolverError::VariableAlreadyExists(name, 4) if name == "b"
+        ));
+        assert_eq!(error.line(), Some(4));
+
+        assert!(matches!(
+            resolve("print 1;\nwhile (true)\n  return;"),
+            Err(ResolverError::ReturnNotInFunction(3))
+        ));
+        assert!(matches!(
+            resolve("\nclass A < A {}"),
+            Err(ResolverError::SelfInheritance(_, 2))
+        ));
+    }
+
+    #[test]
+    fn variables_cannot_read_themselves_while_initialized() {
+        assert!(matches!(
+            resolve("var a = a;"),
+            Err(ResolverError::NotInitialized(name)) if name == "a"
+        ));
+        assert!(matches!(
+            resolve("{\n  var a = a;\n}"),
+            Err(ResolverError::NotInitialized(name)) if name == "a"
+        ));
+
+        /* Redeclared globals read the earlier one, and functions may refer to themselves */
+        assert!(resolve("var a = 1;\nvar a = a + 1;").is_ok());
+        assert!(resolve("var clock = clock;").is_ok());
+        assert!(resolve("var f = () => f;").is_ok());
+    }
+}
@@ -0,0 +1,264 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn lox(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lox-interpreter"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("Failed to run the interpreter binary")
+}
+
+#[test]
+fn ast_json_matches_golden_file() {
+    let output = lox(&["--ast-json", "tests/fixtures/ast.lox"]);
+    assert!(output.status.success());
+
+    let expected = include_str!("fixtures/ast.json");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+}
+
+/// Runs the REPL, feeding it `input` through the standard input.
+fn repl(input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lox-interpreter"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to run the interpreter binary");
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn repl_vars_lists_globals() {
+    let output = repl("var answer = 42;\n:vars\n:exit\nprint answer;\n");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("answer\n"));
+    assert!(!stdout.contains("clock"));
+    assert!(!stdout.contains("42"));
+}
+
+#[test]
+fn repl_continues_unclosed_input() {
+    let output = repl("fun twice(x) {\n  return x * 2;\n}\nprint twice(\n  21);\n");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, ">......>...42\n>");
+}
+
+#[test]
+fn json_message_format_reports_parse_errors() {
+    let output = lox(&["--message-format=json", "tests/fixtures/parse_error.lox"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let diagnostic: serde_json::Value = serde_json::from_str(stderr.trim()).unwrap();
+    assert_eq!(diagnostic["line"], 2);
+    assert_eq!(diagnostic["severity"], "error");
+    assert_eq!(diagnostic["stage"], "parse");
+    assert_eq!(diagnostic["message"], "Invalid assignment target: 2.");
+}
+
+#[test]
+fn json_message_format_reports_declaration_errors_once() {
+    let output = lox(&["--message-format=json", "-e", "var 1 = 2;"]);
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr.lines().count(), 1, "{stderr}");
+    let diagnostic: serde_json::Value = serde_json::from_str(&stderr).unwrap();
+    assert_eq!(diagnostic["stage"], "parse");
+    assert_eq!(diagnostic["line"], 1);
+}
+
+#[test]
+fn warn_precision_flags_unsafe_integers() {
+    let output = lox(&["--warn-precision", "tests/fixtures/precision.lox"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "9007199254740991\n9007199254740992\n"
+    );
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr,
+        "[line 3] Warning: 9007199254740992 is beyond 2^53, where integers lose precision\n"
+    );
+
+    let output = lox(&["tests/fixtures/precision.lox"]);
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn warn_precision_flags_literal_arithmetic() {
+    let output = lox(&["--warn-precision", "-e", "print 9007199254740992 + 1;"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "[line 1] Warning: 9007199254740992 is beyond 2^53, where integers lose precision\n"
+    );
+}
+
+#[test]
+fn check_reports_errors_without_running() {
+    let output = lox(&["--check", "tests/fixtures/resolver_error.lox"]);
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("Resolver error:"));
+    assert!(!stdout.contains("not run"));
+
+    let output = lox(&["--check", "tests/fixtures/ast.lox"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn time_reports_phase_durations() {
+    let output = lox(&["--time", "tests/fixtures/ast.lox"]);
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    for phase in ["scan", "parse", "resolve", "interpret"] {
+        assert!(
+            stderr.contains(&format!("[time] {phase}: ")),
+            "missing {phase} in {stderr}"
+        );
+    }
+}
+
+#[test]
+fn repl_try_discards_globals() {
+    let output = repl(
+        "var kept = 1;\n:try var added = 2; kept = 3; print added + kept;\n:vars\nprint kept;\n",
+    );
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("5\n"));
+    assert!(!stdout.contains("added"));
+    assert!(stdout.contains(">1\n"));
+}
+
+#[test]
+fn repl_forgets_globals_of_failed_inputs() {
+    let output = repl("var b = undefined;\nvar b = b;\n");
+    assert!(output.status.success());
+
+    let output =
+        String::from_utf8(output.stdout).unwrap() + &String::from_utf8(output.stderr).unwrap();
+    assert!(
+        output.contains("Variable b cannot be read before it is initialized"),
+        "{output}"
+    );
+}
+
+#[test]
+fn repl_reports_lines_within_multi_line_input() {
+    let output = repl("fun f() {\n  var a = 1;\n  print a +;\n}\n");
+    assert!(output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("in line 3"), "{stderr}");
+}
+
+#[test]
+fn implicit_semicolons_are_opt_in() {
+    let output = lox(&[
+        "--implicit-semicolons",
+        "tests/fixtures/implicit_semicolons.lox",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "hello!\n");
+
+    let output = lox(&["tests/fixtures/implicit_semicolons.lox"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn eval_runs_an_inline_program() {
+    let output = lox(&["-e", "print 1 + 2;"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "3\n");
+
+    let output = lox(&["--eval=var a = \"a\"; print a + \"b\";"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "ab\n");
+
+    let output = lox(&["-e", "exit(3);"]);
+    assert_eq!(output.status.code(), Some(3));
+
+    let output = lox(&["-e", "print 1 +;"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn folded_literals_keep_their_decimal_point() {
+    let output = lox(&["-e", "print -5.0;\nprint (5.0);\nprint 10.0 / 2.0;"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "-5.0\n5.0\n5\n");
+}
+
+#[test]
+fn constant_division_by_zero_is_a_compile_error() {
+    let output = lox(&["--no-color", "-e", "print \"not run\";\nprint 1 / (2 - 2);"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Division by zero in line 2\n"
+    );
+
+    let output = lox(&["--ieee-division", "-e", "print 1 / 0;"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "inf\n");
+}
+
+#[test]
+fn colors_can_be_forced_or_disabled() {
+    let output = lox(&["--color=always", "tests/fixtures/parse_error.lox"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("\x1b[31m"), "{stderr:?}");
+
+    for flag in ["--no-color", "--color=never"] {
+        let output = lox(&[flag, "tests/fixtures/parse_error.lox"]);
+        assert!(!output.status.success());
+        assert!(!output.stderr.is_empty());
+        assert!(!output.stderr.contains(&0x1b));
+    }
+
+    let output = lox(&["--color=sometimes", "tests/fixtures/parse_error.lox"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn trace_and_shadow_warnings_leave_the_prelude_out() {
+    let output = lox(&["--no-color", "--trace", "-e", "print 1;"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[line 1] print 1;\n1\n"
+    );
+
+    let output = lox(&[
+        "--no-color",
+        "--warn-shadowing",
+        "-e",
+        "{ var identity = 1; }",
+    ]);
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Warning: Variable identity declared in line 1 shadows the one of an enclosing scope\n"
+    );
+}
@@ -0,0 +1,99 @@
+use crate::scanner::error::ErrorType;
+
+/// Replaces the control characters in `raw` with their escape sequences, so that the text can
+/// be safely displayed in a terminal. This is not the inverse of [`unescape`]: backslashes are
+/// kept as they are.
+pub fn escape_control(raw: &str) -> String {
+    let mut result = String::with_capacity(raw.len());
+
+    for c in raw.chars() {
+        match c {
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            '\0' => result.push_str("\\0"),
+            c if c.is_control() => result.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Turns `raw` into a string literal, quotes included, whose contents [`unescape`] decodes back
+/// into `raw`.
+pub fn quote(raw: &str) -> String {
+    let escaped = raw.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\"", escape_control(&escaped))
+}
+
+/// Decodes the escape sequences found in the contents of a string or character literal.
+///
+/// Supported sequences are `\n`, `\t`, `\r`, `\0`, `\\`, `\'`, `\"`, the ASCII hexadecimal
+/// escape `\x41` and the unicode escape `\u{41}`. A backslash right before a line break removes
+/// the line break, so long strings can be split across lines.
+pub fn unescape(raw: &str) -> Result<String, ErrorType> {
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        let escaped = match chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('\'') => '\'',
+            Some('"') => '"',
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(byte) if digits.len() == 2 && byte.is_ascii() => byte as char,
+                    _ => return Err(ErrorType::InvalidEscape(format!("\\x{digits}"))),
+                }
+            }
+            Some('u') => unescape_unicode(&mut chars)?,
+            Some('\n') => continue,
+            Some('\r') if chars.as_str().starts_with('\n') => {
+                chars.next();
+                continue;
+            }
+            Some(other) => return Err(ErrorType::InvalidEscape(format!("\\{other}"))),
+            None => return Err(ErrorType::InvalidEscape(String::from("\\"))),
+        };
+        result.push(escaped);
+    }
+
+    Ok(result)
+}
+
+/// Decodes the `{XXXX}` part of an unicode escape, which may contain from 1 to 6 hex digits.
+fn unescape_unicode(chars: &mut std::str::Chars) -> Result<char, ErrorType> {
+    if chars.next() != Some('{') {
+        return Err(ErrorType::InvalidEscape(String::from("\\u")));
+    }
+
+    let mut digits = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(c) => digits.push(c),
+            None => return Err(ErrorType::InvalidEscape(format!("\\u{{{digits}"))),
+        }
+    }
+
+    let invalid = || ErrorType::InvalidEscape(format!("\\u{{{digits}}}"));
+    if digits.is_empty() || digits.len() > 6 {
+        return Err(invalid());
+    }
+
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(invalid)
+}
@@ -1,61 +1,177 @@
+use crate::statement::Statement;
 use crate::token::Token;
 use std::fmt::{Debug, Formatter, Write};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Variable {
     pub token: Token,
 }
 
+/// The part of the source an expression covers. `start` and `end` are the lines of its first
+/// and last tokens, and `start_offset..end_offset` the bytes from the first byte of its first
+/// token to the last byte of its last one, so a diagnostic can point at the exact spot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub start_offset: usize,
+    pub end_offset: usize,
+}
+
+/// How a number literal was written. Whole numbers print without a decimal point, unless the
+/// literal had one, so `5.0` keeps showing as `5.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum NumberForm {
+    /// Written without a decimal point, or computed, like the results of constant folding.
+    Plain,
+    Decimal,
+}
+
+impl NumberForm {
+    /// Formats `n` the way it was written.
+    pub fn format(self, n: f64) -> String {
+        match self {
+            NumberForm::Decimal if n.is_finite() && n.fract() == 0.0 => format!("{n:.1}"),
+            _ => n.to_string(),
+        }
+    }
+}
+
+impl Span {
+    /// Returns the smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Self {
+        Self {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+            start_offset: self.start_offset.min(other.start_offset),
+            end_offset: self.end_offset.max(other.end_offset),
+        }
+    }
+}
+
+impl From<&Token> for Span {
+    fn from(token: &Token) -> Self {
+        Self {
+            start: token.line(),
+            end: token.line(),
+            start_offset: token.offset(),
+            end_offset: token.offset() + token.lexeme().len(),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Expression {
     Binary {
         left: Box<Expression>,
         operator: Token,
         right: Box<Expression>,
+        span: Span,
+    },
+    Ternary {
+        condition: Box<Expression>,
+        then_expr: Box<Expression>,
+        else_expr: Box<Expression>,
+        span: Span,
     },
-    Grouping(Box<Expression>),
-    Unary(Token, Box<Expression>),
-    Var(Variable),
+    Grouping(Box<Expression>, Span),
+    Unary(Token, Box<Expression>, Span),
+    /// `++target` or `--target`, where the target is a variable or a property.
+    Prefix {
+        operator: Token,
+        target: Box<Expression>,
+        span: Span,
+    },
+    /// `target++` or `target--`, where the target is a variable or a property.
+    Postfix {
+        target: Box<Expression>,
+        operator: Token,
+        span: Span,
+    },
+    Var(Variable, Span),
     Assignment {
         name: String,
         value: Box<Expression>,
         token: Token,
+        span: Span,
     },
     Or {
         left: Box<Expression>,
         right: Box<Expression>,
+        span: Span,
     },
     And {
         left: Box<Expression>,
         right: Box<Expression>,
+        span: Span,
     },
     Call {
         callee: Box<Expression>,
         paren: Token,
         args: Vec<Expression>,
+        span: Span,
     },
     Get {
         expression: Box<Expression>,
         token: Token,
+        span: Span,
+    },
+    /// `object[index]`, which reads a byte of a bytes value.
+    Index {
+        object: Box<Expression>,
+        bracket: Token,
+        index: Box<Expression>,
+        span: Span,
     },
     Set {
         name: Token,
         object: Box<Expression>,
         value: Box<Expression>,
+        span: Span,
+    },
+    /// `target &&= value` or `target ||= value`, where the target is a property. On variables
+    /// these are desugared into `&&` and `||`, but on properties that would evaluate the object
+    /// twice.
+    LogicalSet {
+        target: Box<Expression>,
+        operator: Token,
+        value: Box<Expression>,
+        span: Span,
     },
     This {
         keyword: Token,
+        span: Span,
     },
+    /// A block in expression position, such as `{ var t = 2; t * 2 }`, which evaluates to its
+    /// trailing expression. The block gets its own scope: the statements and the trailing
+    /// expression can see the enclosing variables, but anything declared inside the block is
+    /// discarded once it has been evaluated. `return` may not be used to leave the block.
+    Block(Vec<Statement>, Box<Expression>, Span),
     Super {
         keyword: Token,
+        span: Span,
+    },
+    /// The comma operator, as in `(a, b, c)`: every expression is evaluated from left to right
+    /// and the value of the last one is the result. Always holds at least two expressions.
+    Comma(Vec<Expression>, Span),
+    /// An arrow function, such as `(a, b) => a + b`. Its body is a `return` of the expression
+    /// after the arrow.
+    Lambda {
+        parameters: Vec<Token>,
+        body: Vec<Statement>,
+        span: Span,
     },
 
     // Literals
-    True,
-    False,
-    Number(ordered_float::OrderedFloat<f64>),
-    String(String),
-    Nil,
+    True(Span),
+    False(Span),
+    Number(ordered_float::OrderedFloat<f64>, NumberForm, Span),
+    String(String, Span),
+    Nil(Span),
 }
 
 fn parenthesize(
@@ -78,49 +194,127 @@ fn parenthesize(
 impl Debug for Expression {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Expression::True => f.write_str("true"),
-            Expression::False => f.write_str("false"),
-            Expression::Nil => f.write_str("nil"),
-            Expression::Number(num) => f.write_str(&num.to_string()),
-            Expression::String(str) => f.write_str(str),
+            Expression::True(_) => f.write_str("true"),
+            Expression::False(_) => f.write_str("false"),
+            Expression::Nil(_) => f.write_str("nil"),
+            Expression::Number(num, form, _) => f.write_str(&form.format(num.0)),
+            Expression::String(str, _) => f.write_str(str),
             Expression::Binary {
                 left,
                 operator,
                 right,
+                ..
             } => parenthesize(f, operator.lexeme(), &[left, right]),
-            Expression::Grouping(expr) => parenthesize(f, "group", &[expr]),
-            Expression::Unary(token, expr) => parenthesize(f, token.lexeme(), &[expr]),
-            Expression::Var(variable) => write!(f, "Var({})", variable.token.lexeme()),
-            Expression::Assignment {
-                name: _,
-                value,
-                token: _,
-            } => write!(f, "Assign(name = {value:?})"),
-            Expression::Or { left, right } => {
+            Expression::Ternary {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => parenthesize(f, "?:", &[condition, then_expr, else_expr]),
+            Expression::Grouping(expr, _) => parenthesize(f, "group", &[expr]),
+            Expression::Unary(token, expr, _) => parenthesize(f, token.lexeme(), &[expr]),
+            Expression::Prefix {
+                operator, target, ..
+            } => parenthesize(f, operator.lexeme(), &[target]),
+            Expression::Postfix {
+                target, operator, ..
+            } => {
+                write!(f, "({target:?} {})", operator.lexeme())
+            }
+            Expression::Var(variable, _) => write!(f, "Var({})", variable.token.lexeme()),
+            Expression::Assignment { value, .. } => write!(f, "Assign(name = {value:?})"),
+            Expression::Or { left, right, .. } => {
                 write!(f, "({left:?}) || ({right:?})")
             }
-            Expression::And { left, right } => {
+            Expression::And { left, right, .. } => {
                 write!(f, "({left:?}) && ({right:?})")
             }
-            Expression::Call {
-                callee,
-                paren: _,
-                args,
-            } => {
+            Expression::Call { callee, args, .. } => {
                 write!(f, "call (callee: {callee:?}, args: {args:?})")
             }
-            Expression::Get { expression, token } => {
+            Expression::Get {
+                expression, token, ..
+            } => {
                 write!(f, "get(expr: {expression:?}, name: {})", token.lexeme())
             }
+            Expression::Index { object, index, .. } => {
+                write!(f, "index(expr: {object:?}, index: {index:?})")
+            }
             Expression::Set {
                 name,
                 object,
                 value,
+                ..
             } => {
                 write!(f, "set(name: {name}, object: {object:?}, value: {value:?})")
             }
+            Expression::LogicalSet {
+                target,
+                operator,
+                value,
+                ..
+            } => parenthesize(f, operator.lexeme(), &[target, value]),
             Expression::This { .. } => write!(f, "this"),
+            Expression::Block(statements, value, _) => {
+                f.write_str("(block")?;
+                for statement in statements {
+                    write!(f, " {statement}")?;
+                }
+                write!(f, " {value:?})")
+            }
             Expression::Super { .. } => write!(f, "super"),
+            Expression::Comma(expressions, _) => {
+                let expressions: Vec<&Expression> = expressions.iter().collect();
+                parenthesize(f, ",", &expressions)
+            }
+            Expression::Lambda {
+                parameters, body, ..
+            } => {
+                let parameters: Vec<&str> = parameters.iter().map(Token::lexeme).collect();
+                write!(f, "(=> ({})", parameters.join(", "))?;
+                for statement in body {
+                    write!(f, " {statement}")?;
+                }
+                f.write_char(')')
+            }
         }
     }
 }
+
+impl Expression {
+    /// Returns the part of the source the expression was parsed from.
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Binary { span, .. }
+            | Expression::Ternary { span, .. }
+            | Expression::Grouping(_, span)
+            | Expression::Unary(_, _, span)
+            | Expression::Prefix { span, .. }
+            | Expression::Postfix { span, .. }
+            | Expression::Var(_, span)
+            | Expression::Assignment { span, .. }
+            | Expression::Or { span, .. }
+            | Expression::And { span, .. }
+            | Expression::Call { span, .. }
+            | Expression::Get { span, .. }
+            | Expression::Index { span, .. }
+            | Expression::Set { span, .. }
+            | Expression::LogicalSet { span, .. }
+            | Expression::This { span, .. }
+            | Expression::Block(_, _, span)
+            | Expression::Super { span, .. }
+            | Expression::Comma(_, span)
+            | Expression::Lambda { span, .. }
+            | Expression::True(span)
+            | Expression::False(span)
+            | Expression::Number(_, _, span)
+            | Expression::String(_, span)
+            | Expression::Nil(span) => *span,
+        }
+    }
+
+    /// Returns the line the expression starts in.
+    pub fn line(&self) -> usize {
+        self.span().start
+    }
+}
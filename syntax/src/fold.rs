@@ -0,0 +1,343 @@
+use crate::expression::{Expression, NumberForm};
+use crate::statement::Statement;
+use crate::token::TokenType;
+use thiserror::Error;
+
+/// The largest integer that, along with every integer below it, an `f64` represents exactly.
+pub const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+#[derive(Error, Debug)]
+pub enum FoldError {
+    /// A division whose divisor is a constant zero, such as `1 / (2 - 2)`.
+    #[error("Division by zero in line {0}")]
+    DivisionByZero(usize),
+}
+
+impl FoldError {
+    pub fn line(&self) -> usize {
+        match self {
+            FoldError::DivisionByZero(line) => *line,
+        }
+    }
+}
+
+/// Folds the arithmetic over number literals in the statements, so `2 + 3 * 4` becomes
+/// `14` before it is ever run. Results beyond [`MAX_SAFE_INTEGER`] are left for the interpreter,
+/// which can warn that they lost precision. Returns the divisions by a constant zero that were found.
+/// They are left unfolded, because they are only errors when the interpreter does not
+/// follow IEEE-754 division.
+pub fn fold_constants(statements: &mut [Statement]) -> Vec<FoldError> {
+    let mut errors = Vec::new();
+    fold_statements(statements, &mut errors);
+    errors
+}
+
+fn fold_statements(statements: &mut [Statement], errors: &mut Vec<FoldError>) {
+    for statement in statements {
+        fold_statement(statement, errors);
+    }
+}
+
+fn fold_statement(statement: &mut Statement, errors: &mut Vec<FoldError>) {
+    match statement {
+        Statement::Expression(expression) => fold_expression(expression, errors),
+        Statement::Print(expressions) => {
+            for expression in expressions {
+                fold_expression(expression, errors);
+            }
+        }
+        Statement::VariableDeclaration { initializer, .. } => {
+            if let Some(initializer) = initializer {
+                fold_expression(initializer, errors);
+            }
+        }
+        Statement::FunctionDeclaration(function) => fold_statements(&mut function.body, errors),
+        Statement::Block { statements, .. } => fold_statements(statements, errors),
+        Statement::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            fold_expression(condition, errors);
+            fold_statement(then_branch, errors);
+            if let Some(else_branch) = else_branch {
+                fold_statement(else_branch, errors);
+            }
+        }
+        Statement::While {
+            condition,
+            body,
+            else_branch,
+            ..
+        } => {
+            fold_expression(condition, errors);
+            fold_statement(body, errors);
+            if let Some(else_branch) = else_branch {
+                fold_statement(else_branch, errors);
+            }
+        }
+        Statement::For {
+            initializer,
+            condition,
+            increment,
+            body,
+            else_branch,
+            ..
+        } => {
+            if let Some(initializer) = initializer {
+                fold_statement(initializer, errors);
+            }
+            if let Some(condition) = condition {
+                fold_expression(condition, errors);
+            }
+            if let Some(increment) = increment {
+                fold_expression(increment, errors);
+            }
+            fold_statement(body, errors);
+            if let Some(else_branch) = else_branch {
+                fold_statement(else_branch, errors);
+            }
+        }
+        Statement::ClassDeclaration {
+            methods,
+            static_fields,
+            static_methods,
+            ..
+        } => {
+            for initializer in static_fields.iter_mut().flat_map(|f| &mut f.initializer) {
+                fold_expression(initializer, errors);
+            }
+            for method in methods.iter_mut().chain(static_methods) {
+                fold_statements(&mut method.body, errors);
+            }
+        }
+        Statement::Return { expression, .. } => {
+            if let Some(expression) = expression {
+                fold_expression(expression, errors);
+            }
+        }
+        Statement::Throw { expression, .. } => fold_expression(expression, errors),
+        Statement::With { resource, body, .. } => {
+            fold_expression(resource, errors);
+            fold_statements(body, errors);
+        }
+        Statement::Match { value, arms, .. } => {
+            fold_expression(value, errors);
+            for arm in arms {
+                fold_statement(&mut arm.body, errors);
+            }
+        }
+        Statement::Try {
+            body,
+            catch_body,
+            finally_body,
+            ..
+        } => {
+            fold_statements(body, errors);
+            fold_statements(catch_body, errors);
+            if let Some(finally_body) = finally_body {
+                fold_statements(finally_body, errors);
+            }
+        }
+        Statement::Break { .. } | Statement::Continue { .. } => {}
+    }
+}
+
+fn fold_expression(expression: &mut Expression, errors: &mut Vec<FoldError>) {
+    match expression {
+        Expression::Binary { left, right, .. } => {
+            fold_expression(left, errors);
+            fold_expression(right, errors);
+        }
+        Expression::Ternary {
+            condition,
+            then_expr,
+            else_expr,
+            ..
+        } => {
+            fold_expression(condition, errors);
+            fold_expression(then_expr, errors);
+            fold_expression(else_expr, errors);
+        }
+        Expression::Grouping(inner, _) => fold_expression(inner, errors),
+        Expression::Unary(_, inner, _) => fold_expression(inner, errors),
+        Expression::Assignment { value, .. } => fold_expression(value, errors),
+        Expression::Or { left, right, .. } | Expression::And { left, right, .. } => {
+            fold_expression(left, errors);
+            fold_expression(right, errors);
+        }
+        Expression::Call { callee, args, .. } => {
+            fold_expression(callee, errors);
+            for arg in args {
+                fold_expression(arg, errors);
+            }
+        }
+        Expression::Get { expression, .. } => fold_expression(expression, errors),
+        Expression::Index { object, index, .. } => {
+            fold_expression(object, errors);
+            fold_expression(index, errors);
+        }
+        Expression::Set { object, value, .. } => {
+            fold_expression(object, errors);
+            fold_expression(value, errors);
+        }
+        Expression::LogicalSet { target, value, .. } => {
+            fold_expression(target, errors);
+            fold_expression(value, errors);
+        }
+        Expression::Block(statements, value, _) => {
+            fold_statements(statements, errors);
+            fold_expression(value, errors);
+        }
+        Expression::Comma(expressions, _) => {
+            for expression in expressions {
+                fold_expression(expression, errors);
+            }
+        }
+        Expression::Lambda { body, .. } => fold_statements(body, errors),
+        Expression::Prefix { .. }
+        | Expression::Postfix { .. }
+        | Expression::Var(..)
+        | Expression::This { .. }
+        | Expression::Super { .. }
+        | Expression::True(_)
+        | Expression::False(_)
+        | Expression::Number(..)
+        | Expression::String(..)
+        | Expression::Nil(_) => {}
+    }
+
+    if let Expression::Binary {
+        left,
+        operator,
+        right,
+        ..
+    } = expression
+        && *operator.token_type() == TokenType::Slash
+        && literal(left).is_some()
+        && literal(right) == Some(0.0)
+    {
+        errors.push(FoldError::DivisionByZero(operator.line()));
+    }
+
+    if !matches!(expression, Expression::Number(..))
+        && let Some((value, form)) = constant_value(expression)
+    {
+        *expression = Expression::Number(value.into(), form, expression.span());
+    }
+}
+
+/// Returns the value of an expression whose operands have already been folded, if it is
+/// arithmetic over number literals, along with the form to print it in. Negating or grouping
+/// a literal keeps the form it was written in, so `-5.0` keeps printing as `-5.0`.
+fn constant_value(expression: &Expression) -> Option<(f64, NumberForm)> {
+    match expression {
+        Expression::Number(n, form, _) => Some((n.0, *form)),
+        Expression::Grouping(inner, _) => constant_value(inner),
+        Expression::Unary(operator, inner, _) if *operator.token_type() == TokenType::Minus => {
+            constant_value(inner).map(|(n, form)| (-n, form))
+        }
+        Expression::Binary {
+            left,
+            operator,
+            right,
+            ..
+        } => {
+            let (left, right) = (literal(left)?, literal(right)?);
+            let result = match operator.token_type() {
+                TokenType::Plus => left + right,
+                TokenType::Minus => left - right,
+                TokenType::Star => left * right,
+                TokenType::Slash if right != 0.0 => left / right,
+                _ => return None,
+            };
+            (!result.is_finite() || result.abs() <= MAX_SAFE_INTEGER)
+                .then_some((result, NumberForm::Plain))
+        }
+        _ => None,
+    }
+}
+
+fn literal(expression: &Expression) -> Option<f64> {
+    match expression {
+        Expression::Number(n, ..) => Some(n.0),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FoldError, fold_constants};
+    use crate::{Parser, Scanner, Statement};
+    use std::io::Cursor;
+
+    fn fold(source: &str) -> String {
+        let tokens = Scanner::new(Cursor::new(source)).scan_tokens().unwrap();
+        let mut statements = Parser::new(&tokens).statements().unwrap();
+        assert!(fold_constants(&mut statements).is_empty());
+
+        let folded: Vec<String> = statements.iter().map(Statement::to_string).collect();
+        folded.join("\n")
+    }
+
+    #[test]
+    fn folds_number_arithmetic() {
+        assert_eq!(fold("print 2 + 3 * 4;"), "print 14;");
+        assert_eq!(fold("print (1 + 2) * -(4 - 1);"), "print -9;");
+        assert_eq!(fold("var x = 10 / 4;"), "var x = 2.5;");
+    }
+
+    #[test]
+    fn keeps_non_constant_operands() {
+        assert_eq!(fold("print x + 2 * 3;"), "print (+ Var(x) 6);");
+        assert_eq!(fold("print \"a\" + 1;"), "print (+ a 1);");
+        assert_eq!(fold("print 1 < 2;"), "print (< 1 2);");
+    }
+
+    fn division_by_zero(source: &str) -> (String, Vec<usize>) {
+        let tokens = Scanner::new(Cursor::new(source)).scan_tokens().unwrap();
+        let mut statements = Parser::new(&tokens).statements().unwrap();
+        let errors = fold_constants(&mut statements);
+
+        let lines = errors.iter().map(FoldError::line).collect();
+        (statements[0].to_string(), lines)
+    }
+
+    #[test]
+    fn reports_division_by_zero() {
+        assert_eq!(
+            division_by_zero("print 1 / 0;"),
+            ("print (/ 1 0);".to_string(), vec![1])
+        );
+        assert_eq!(
+            division_by_zero("print 1 +\n1 / (2 - 2);"),
+            ("print (+ 1 (/ 1 0));".to_string(), vec![2])
+        );
+        assert_eq!(
+            division_by_zero("print x / 0;"),
+            ("print (/ Var(x) 0);".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn keeps_results_that_lose_precision() {
+        assert_eq!(
+            fold("print 9007199254740992 + 1;"),
+            "print (+ 9007199254740992 1);"
+        );
+        assert_eq!(
+            fold("print 9007199254740990 + 1;"),
+            "print 9007199254740991;"
+        );
+    }
+
+    #[test]
+    fn keeps_the_form_of_literals() {
+        assert_eq!(fold("print 5.0;"), "print 5.0;");
+        assert_eq!(fold("print -5.0;"), "print -5.0;");
+        assert_eq!(fold("print (5.0);"), "print 5.0;");
+        assert_eq!(fold("print -(-(2.0));"), "print 2.0;");
+        assert_eq!(fold("print 10.0 / 2.0;"), "print 5;");
+    }
+}
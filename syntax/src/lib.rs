@@ -1,11 +1,14 @@
+mod escape;
 pub mod expression;
+pub mod fold;
 pub mod parser;
 mod scanner;
 pub mod statement;
 pub mod token;
 mod utf8;
 
-pub use expression::Expression;
+pub use escape::quote;
+pub use expression::{Expression, NumberForm, Span};
 pub use parser::Parser;
 pub use scanner::Scanner;
 pub use scanner::{ScannerResult, error::ScannerError};
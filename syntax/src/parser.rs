@@ -1,4 +1,4 @@
-use crate::expression::{self, Expression};
+use crate::expression::{self, Expression, NumberForm, Span};
 use crate::statement;
 use crate::statement::{Block, Statement};
 use crate::token::{Token, TokenType};
@@ -9,19 +9,82 @@ const MAX_ARGS: usize = 255;
 
 #[derive(Error, Debug)]
 pub enum ParserError {
-    #[error("Expected: {0:?}")]
-    FailedMatch(TokenType),
+    /// A token other than the expected one, found in `line`. The line is only missing when the
+    /// source has no tokens at all.
+    #[error("Expected: {expected:?}{}", in_line(*.line))]
+    FailedMatch {
+        expected: TokenType,
+        line: Option<usize>,
+    },
     #[error("Invalid assignment target: {0:?}.")]
-    InvalidAssignmentTarget(Expression),
+    InvalidAssignmentTarget(Box<Expression>),
     #[error("Token {0:?} has too many arguments (max: {MAX_ARGS})")]
     TooManyArgs(Token),
+    #[error("Invalid target for the {} operator in line {}", .0.lexeme(), .0.line())]
+    InvalidIncrementTarget(Token),
+    #[error("Invalid arrow function parameter: {0:?}.")]
+    InvalidLambdaParameter(Box<Expression>),
+    #[error("The source ended unexpectedly")]
+    UnexpectedEnd,
+    /// A `match` pattern other than a literal or `_`.
+    #[error("Invalid match pattern: {0:?}.")]
+    InvalidPattern(Box<Expression>),
+}
+
+impl ParserError {
+    /// Returns the line the error was found in, if the error knows it.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ParserError::FailedMatch { line, .. } => *line,
+            ParserError::UnexpectedEnd => None,
+            ParserError::InvalidAssignmentTarget(expression)
+            | ParserError::InvalidLambdaParameter(expression)
+            | ParserError::InvalidPattern(expression) => Some(expression.line()),
+            ParserError::TooManyArgs(token) | ParserError::InvalidIncrementTarget(token) => {
+                Some(token.line())
+            }
+        }
+    }
+}
+
+fn in_line(line: Option<usize>) -> String {
+    line.map(|line| format!(" in line {line}"))
+        .unwrap_or_default()
 }
 
 type ParserResult<T> = Result<T, ParserError>;
 
+#[derive(Clone, Copy)]
+enum Associativity {
+    Left,
+}
+
+/// The precedence and associativity of every binary operator, from the loosest (`or`) to the
+/// tightest (`*` and `/`). A new binary operator only needs an entry here.
+fn binary_operator(token_type: &TokenType) -> Option<(u8, Associativity)> {
+    use TokenType::*;
+
+    match token_type {
+        Or => Some((1, Associativity::Left)),
+        And => Some((2, Associativity::Left)),
+        BangEqual | EqualEqual => Some((3, Associativity::Left)),
+        Greater | GreaterEqual | Less | LessEqual => Some((4, Associativity::Left)),
+        Minus | Plus => Some((5, Associativity::Left)),
+        Slash | Star => Some((6, Associativity::Left)),
+        _ => None,
+    }
+}
+
+/// Parses statements out of tokens, which are pulled one at a time as parsing goes.
 pub struct Parser<'a> {
-    tokens: &'a [Token],
-    current: usize,
+    tokens: Box<dyn Iterator<Item = Token> + 'a>,
+    previous: Option<Token>,
+    next: Option<Token>,
+    implicit_semicolons: bool,
+    /// Whether the statement about to be parsed ends the then branch of an `if`. A loop there
+    /// leaves its `else` to the `if`, so `if (a) while (b) {} else c;` keeps meaning what it
+    /// did before loops had an `else`.
+    in_then_branch: bool,
 }
 
 macro_rules! match_token {
@@ -58,7 +121,7 @@ macro_rules! check_token {
 macro_rules! expect_token {
     ($parser: ident, $pattern: pat, $token_type: ident) => {{
         if !(match_token!($parser, $pattern)) {
-            return Err(ParserError::FailedMatch(TokenType::$token_type));
+            return Err($parser.failed_match(TokenType::$token_type));
         }
     }};
 }
@@ -67,7 +130,7 @@ macro_rules! expect_token_with_param {
     ($parser: ident, $pattern: pat, $token_type: ident, $params: expr) => {{
         {
             if !(match_token!($parser, $pattern)) {
-                return Err(ParserError::FailedMatch(TokenType::$token_type($params)));
+                return Err($parser.failed_match(TokenType::$token_type($params)));
             }
             $parser.previous().unwrap()
         }
@@ -87,7 +150,31 @@ macro_rules! expect_identifier {
 
 impl<'a> Parser<'a> {
     pub fn new(tokens: &'a [Token]) -> Self {
-        Self { tokens, current: 0 }
+        Self::from_tokens(tokens.iter().cloned())
+    }
+
+    /// Creates a parser that pulls its tokens lazily from `tokens`, so they never have to be
+    /// collected all at once. Together with the [`Scanner`](crate::Scanner) iterator, this lets
+    /// large sources be parsed as a stream.
+    pub fn from_tokens(tokens: impl IntoIterator<Item = Token> + 'a) -> Self {
+        let mut tokens = Box::new(tokens.into_iter());
+        let next = tokens.next();
+
+        Self {
+            tokens,
+            previous: None,
+            next,
+            implicit_semicolons: false,
+            in_then_branch: false,
+        }
+    }
+
+    /// When enabled, the semicolon that ends a statement may be left out if the statement is
+    /// the last one on its line, in its block, or in the source. The parser only checks for the
+    /// end of a statement once it could not continue the expression, so the token that follows
+    /// always starts something new. Off by default.
+    pub fn set_implicit_semicolons(&mut self, implicit_semicolons: bool) {
+        self.implicit_semicolons = implicit_semicolons;
     }
 
     pub fn statements(&mut self) -> ParserResult<Vec<Statement>> {
@@ -103,10 +190,8 @@ impl<'a> Parser<'a> {
             Ok(Statement::FunctionDeclaration(self.function_declaration()?))
         } else if match_token!(self, TokenType::Var) {
             /* Synchronize if parsing a variable declaration failed */
-            self.variable_declaration().inspect_err(|e| {
-                eprintln!("{e}");
-                self.synchronize();
-            })
+            self.variable_declaration()
+                .inspect_err(|_| self.synchronize())
         } else if match_token!(self, TokenType::Class) {
             self.class_declaration()
         } else {
@@ -115,13 +200,17 @@ impl<'a> Parser<'a> {
     }
 
     fn class_declaration(&mut self) -> ParserResult<Statement> {
+        let line = self.previous().unwrap().line();
         let name = expect_identifier!(self).lexeme().to_string();
 
         let super_class = if match_token!(self, TokenType::Less) {
             let identifier = expect_identifier!(self);
-            Some(Expression::Var(expression::Variable {
-                token: identifier.clone(),
-            }))
+            Some(Expression::Var(
+                expression::Variable {
+                    token: identifier.clone(),
+                },
+                Span::from(identifier),
+            ))
         } else {
             None
         };
@@ -129,9 +218,32 @@ impl<'a> Parser<'a> {
         expect_token!(self, TokenType::LeftBrace, LeftBrace);
 
         let mut methods = Vec::new();
+        let mut static_fields = Vec::new();
+        let mut static_methods = Vec::new();
 
         while !check_token!(self, TokenType::RightBrace) {
-            methods.push(self.function_declaration()?);
+            if match_token!(self, TokenType::Static) {
+                if !match_token!(self, TokenType::Var) {
+                    static_methods.push(self.function_declaration()?);
+                    continue;
+                }
+
+                let Statement::VariableDeclaration {
+                    name,
+                    initializer,
+                    line,
+                } = self.variable_declaration()?
+                else {
+                    unreachable!("variable_declaration only returns variable declarations");
+                };
+                static_fields.push(statement::StaticField {
+                    name,
+                    initializer,
+                    line,
+                });
+            } else {
+                methods.push(self.function_declaration()?);
+            }
         }
 
         expect_token!(self, TokenType::RightBrace, RightBrace);
@@ -139,12 +251,17 @@ impl<'a> Parser<'a> {
         Ok(Statement::ClassDeclaration {
             name,
             methods,
+            static_fields,
+            static_methods,
             super_class,
+            line,
         })
     }
 
     fn function_declaration(&mut self) -> ParserResult<statement::Function> {
-        let name = expect_identifier!(self).lexeme().to_string();
+        let name = expect_identifier!(self);
+        let line = name.line();
+        let name = name.lexeme().to_string();
 
         expect_token!(self, TokenType::LeftParen, LeftParen);
 
@@ -155,8 +272,7 @@ impl<'a> Parser<'a> {
 
             while match_token!(self, TokenType::Comma) {
                 if parameters.len() >= MAX_ARGS {
-                    eprintln!("{}", ParserError::TooManyArgs(self.peek().unwrap().clone()));
-                    break;
+                    return Err(ParserError::TooManyArgs(self.peek().unwrap().clone()));
                 }
 
                 let ident = expect_identifier!(self).clone();
@@ -173,19 +289,21 @@ impl<'a> Parser<'a> {
             name,
             parameters,
             body,
+            line,
         })
     }
 
     fn variable_declaration(&mut self) -> ParserResult<Statement> {
-        let current_token = self.peek().unwrap();
+        let Some(current_token) = self.peek() else {
+            return Err(ParserError::UnexpectedEnd);
+        };
+        let line = current_token.line();
         let name = if let TokenType::Identifier(ident) = current_token.token_type() {
             let ident = ident.clone();
             self.advance();
             ident
         } else {
-            return Err(ParserError::FailedMatch(TokenType::Identifier(
-                String::new(),
-            )));
+            return Err(self.failed_match(TokenType::Identifier(String::new())));
         };
 
         let initializer = if match_token!(self, TokenType::Equal) {
@@ -194,12 +312,19 @@ impl<'a> Parser<'a> {
             None
         };
 
-        expect_token!(self, TokenType::Semicolon, Semicolon);
-        Ok(Statement::VariableDeclaration { name, initializer })
+        self.expect_statement_end()?;
+        Ok(Statement::VariableDeclaration {
+            name,
+            initializer,
+            line,
+        })
     }
 
     fn parse_statement(&mut self) -> ParserResult<Statement> {
-        let token = self.peek().unwrap();
+        let in_then_branch = std::mem::take(&mut self.in_then_branch);
+        let Some(token) = self.peek() else {
+            return Err(ParserError::UnexpectedEnd);
+        };
 
         match token.token_type() {
             TokenType::Print => {
@@ -216,21 +341,37 @@ impl<'a> Parser<'a> {
             }
             TokenType::For => {
                 self.advance();
-                self.parse_for_statement()
+                self.parse_for_statement(in_then_branch)
             }
             TokenType::While => {
                 self.advance();
-                self.parse_while_statement()
+                self.parse_while_statement(in_then_branch)
             }
             TokenType::Return => {
                 self.advance();
                 self.parse_return_statement()
             }
+            TokenType::Throw => {
+                self.advance();
+                self.parse_throw_statement()
+            }
+            TokenType::Try => {
+                self.advance();
+                self.parse_try_statement()
+            }
+            TokenType::With => {
+                self.advance();
+                self.parse_with_statement()
+            }
+            TokenType::Match => {
+                self.advance();
+                self.parse_match_statement()
+            }
             TokenType::Break => {
                 let keyword = token.clone();
 
                 self.advance();
-                expect_token!(self, TokenType::Semicolon, Semicolon);
+                self.expect_statement_end()?;
 
                 Ok(Statement::Break { keyword })
             }
@@ -238,7 +379,7 @@ impl<'a> Parser<'a> {
                 let keyword = token.clone();
 
                 self.advance();
-                expect_token!(self, TokenType::Semicolon, Semicolon);
+                self.expect_statement_end()?;
 
                 Ok(Statement::Continue { keyword })
             }
@@ -248,24 +389,30 @@ impl<'a> Parser<'a> {
 
     fn parse_expression_statement(&mut self) -> ParserResult<Statement> {
         let expression = self.expression()?;
-        expect_token!(self, TokenType::Semicolon, Semicolon);
+        self.expect_statement_end()?;
 
         Ok(Statement::Expression(expression))
     }
 
+    /// The values of a print statement are separated by commas, so each one is parsed with
+    /// [`Parser::assignment`] like call arguments.
     fn parse_print_statement(&mut self) -> ParserResult<Statement> {
-        let expression = self.expression()?;
-        expect_token!(self, TokenType::Semicolon, Semicolon);
+        let mut expressions = vec![self.assignment()?];
+        while match_token!(self, TokenType::Comma) {
+            expressions.push(self.assignment()?);
+        }
+        self.expect_statement_end()?;
 
-        Ok(Statement::Print(expression))
+        Ok(Statement::Print(expressions))
     }
 
     fn parse_block(&mut self) -> ParserResult<Block> {
         let mut statements = Vec::new();
 
-        while !(matches!(self.peek().unwrap().token_type(), TokenType::RightBrace))
-            && !self.is_at_end()
-        {
+        while !check_token!(self, TokenType::RightBrace) {
+            if self.is_at_end() {
+                return Err(ParserError::UnexpectedEnd);
+            }
             statements.push(self.declaration()?);
         }
 
@@ -275,14 +422,20 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_block_statement(&mut self) -> ParserResult<Statement> {
-        Ok(Statement::Block(self.parse_block()?))
+        let line = self.previous().unwrap().line();
+        Ok(Statement::Block {
+            statements: self.parse_block()?,
+            line,
+        })
     }
 
     fn parse_if_statement(&mut self) -> ParserResult<Statement> {
+        let line = self.previous().unwrap().line();
         expect_token!(self, TokenType::LeftParen, LeftParen);
         let condition = self.expression()?;
         expect_token!(self, TokenType::RightParen, RightParen);
 
+        self.in_then_branch = true;
         let then_branch = self.parse_statement()?;
 
         let else_branch = if match_token!(self, TokenType::Else) {
@@ -295,23 +448,40 @@ impl<'a> Parser<'a> {
             condition,
             then_branch: Box::new(then_branch),
             else_branch,
+            line,
         })
     }
 
-    fn parse_while_statement(&mut self) -> ParserResult<Statement> {
+    fn parse_while_statement(&mut self, in_then_branch: bool) -> ParserResult<Statement> {
+        let line = self.previous().unwrap().line();
         expect_token!(self, TokenType::LeftParen, LeftParen);
         let condition = self.expression()?;
         expect_token!(self, TokenType::RightParen, RightParen);
 
+        self.in_then_branch = in_then_branch;
         let body = self.parse_statement()?;
 
         Ok(Statement::While {
             condition,
             body: Box::new(body),
+            else_branch: self.parse_loop_else(in_then_branch)?,
+            line,
         })
     }
 
-    fn parse_for_statement(&mut self) -> ParserResult<Statement> {
+    /// Parses the optional `else` after the body of a loop. A loop that ends the then branch of
+    /// an `if` has no `else`, since it belongs to the `if`. The loop can be wrapped in braces
+    /// to give it one.
+    fn parse_loop_else(&mut self, in_then_branch: bool) -> ParserResult<Option<Box<Statement>>> {
+        if !in_then_branch && match_token!(self, TokenType::Else) {
+            Ok(Some(Box::new(self.parse_statement()?)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_for_statement(&mut self, in_then_branch: bool) -> ParserResult<Statement> {
+        let line = self.previous().unwrap().line();
         expect_token!(self, TokenType::LeftParen, LeftParen);
 
         let initializer = if match_token!(self, TokenType::Semicolon) {
@@ -339,6 +509,7 @@ impl<'a> Parser<'a> {
             inc
         };
 
+        self.in_then_branch = in_then_branch;
         let body = Box::new(self.parse_statement()?);
 
         Ok(Statement::For {
@@ -346,18 +517,20 @@ impl<'a> Parser<'a> {
             condition,
             increment,
             body,
+            else_branch: self.parse_loop_else(in_then_branch)?,
+            line,
         })
     }
 
     fn parse_return_statement(&mut self) -> ParserResult<Statement> {
         let keyword = self.previous().unwrap().clone();
-        let expression = if !check_token!(self, TokenType::Semicolon) {
+        let expression = if !self.at_statement_end() {
             Some(self.expression()?)
         } else {
             None
         };
 
-        expect_token!(self, TokenType::Semicolon, Semicolon);
+        self.expect_statement_end()?;
 
         Ok(Statement::Return {
             expression,
@@ -365,139 +538,235 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn expression(&mut self) -> ParserResult<Expression> {
-        self.assignment()
+    fn parse_throw_statement(&mut self) -> ParserResult<Statement> {
+        let keyword = self.previous().unwrap().clone();
+        let expression = self.expression()?;
+        self.expect_statement_end()?;
+
+        Ok(Statement::Throw {
+            keyword,
+            expression,
+        })
     }
 
-    fn assignment(&mut self) -> ParserResult<Expression> {
-        let expr = self.or()?;
+    fn parse_try_statement(&mut self) -> ParserResult<Statement> {
+        let line = self.previous().unwrap().line();
+        expect_token!(self, TokenType::LeftBrace, LeftBrace);
+        let body = self.parse_block()?;
 
-        if match_token!(self, TokenType::Equal) {
-            let equals = self.previous().unwrap().clone();
-            let value_expr = self.assignment()?;
+        expect_token!(self, TokenType::Catch, Catch);
+        expect_token!(self, TokenType::LeftParen, LeftParen);
+        let catch_name = expect_identifier!(self).clone();
+        expect_token!(self, TokenType::RightParen, RightParen);
 
-            match expr {
-                Expression::Var(variable) => Ok(Expression::Assignment {
-                    name: variable.token.lexeme().into(),
-                    value: Box::new(value_expr),
-                    token: equals.clone(),
-                }),
-                Expression::Get { token, expression } => Ok(Expression::Set {
-                    name: token.clone(),
-                    object: expression,
-                    value: Box::new(value_expr),
-                }),
-                _ => Err(ParserError::InvalidAssignmentTarget(value_expr)),
-            }
+        expect_token!(self, TokenType::LeftBrace, LeftBrace);
+        let catch_body = self.parse_block()?;
+
+        let finally_body = if match_token!(self, TokenType::Finally) {
+            expect_token!(self, TokenType::LeftBrace, LeftBrace);
+            Some(self.parse_block()?)
         } else {
-            Ok(expr)
-        }
+            None
+        };
+
+        Ok(Statement::Try {
+            body,
+            catch_name,
+            catch_body,
+            finally_body,
+            line,
+        })
     }
 
-    fn or(&mut self) -> ParserResult<Expression> {
-        let mut expr = self.and()?;
+    fn parse_match_statement(&mut self) -> ParserResult<Statement> {
+        let keyword = self.previous().unwrap().clone();
+        let value = self.expression()?;
+        expect_token!(self, TokenType::LeftBrace, LeftBrace);
 
-        while match_token!(self, TokenType::Or) {
-            let right = self.and()?;
-            expr = Expression::Or {
-                left: Box::new(expr),
-                right: Box::new(right),
+        let mut arms = Vec::new();
+        while !check_token!(self, TokenType::RightBrace) {
+            let pattern = if match_token!(self, TokenType::Underscore) {
+                None
+            } else {
+                Some(self.match_pattern()?)
             };
+            expect_token!(self, TokenType::Arrow, Arrow);
+            let body = self.parse_statement()?;
+            arms.push(statement::MatchArm { pattern, body });
+
+            /* Arms may be separated by commas, as in `1 => print "one";, _ => {}` */
+            match_token!(self, TokenType::Comma);
         }
+        expect_token!(self, TokenType::RightBrace, RightBrace);
 
-        Ok(expr)
+        Ok(Statement::Match {
+            keyword,
+            value,
+            arms,
+        })
     }
 
-    fn and(&mut self) -> ParserResult<Expression> {
-        let mut expr = self.equality()?;
-
-        while match_token!(self, TokenType::And) {
-            let right = self.equality()?;
-            expr = Expression::And {
-                left: Box::new(expr),
-                right: Box::new(right),
-            };
+    /// Parses the pattern of a `match` arm: a literal, or a negated number.
+    fn match_pattern(&mut self) -> ParserResult<Expression> {
+        let pattern = self.unary()?;
+        match &pattern {
+            Expression::Number(..)
+            | Expression::String(..)
+            | Expression::True(_)
+            | Expression::False(_)
+            | Expression::Nil(_) => Ok(pattern),
+            Expression::Unary(operator, operand, _)
+                if *operator.token_type() == TokenType::Minus
+                    && matches!(**operand, Expression::Number(..)) =>
+            {
+                Ok(pattern)
+            }
+            _ => Err(ParserError::InvalidPattern(Box::new(pattern))),
         }
+    }
 
-        Ok(expr)
+    fn parse_with_statement(&mut self) -> ParserResult<Statement> {
+        let keyword = self.previous().unwrap().clone();
+
+        expect_token!(self, TokenType::LeftParen, LeftParen);
+        let resource = self.expression()?;
+        expect_token!(self, TokenType::RightParen, RightParen);
+
+        expect_token!(self, TokenType::LeftBrace, LeftBrace);
+        let body = self.parse_block()?;
+
+        Ok(Statement::With {
+            keyword,
+            resource,
+            body,
+        })
     }
 
-    fn equality(&mut self) -> ParserResult<Expression> {
-        let mut expression = self.comparison()?;
+    pub fn expression(&mut self) -> ParserResult<Expression> {
+        self.comma()
+    }
 
-        while match_token!(self, TokenType::BangEqual | TokenType::EqualEqual) {
-            let operator = match self.previous() {
-                Some(operator) => operator.clone(),
-                None => break,
-            };
-            let right = self.comparison()?;
+    /// The comma operator has the lowest precedence. Places where commas separate items, such
+    /// as argument lists, parse [`Parser::assignment`] instead so they are not swallowed.
+    fn comma(&mut self) -> ParserResult<Expression> {
+        let expr = self.assignment()?;
 
-            expression = Expression::Binary {
-                left: Box::new(expression),
-                operator,
-                right: Box::new(right),
-            };
+        if !check_token!(self, TokenType::Comma) {
+            return Ok(expr);
         }
 
-        Ok(expression)
+        let mut expressions = vec![expr];
+        while match_token!(self, TokenType::Comma) {
+            expressions.push(self.assignment()?);
+        }
+
+        let span = expressions[0]
+            .span()
+            .to(expressions[expressions.len() - 1].span());
+        Ok(Expression::Comma(expressions, span))
     }
 
-    fn comparison(&mut self) -> ParserResult<Expression> {
-        let mut expression = self.term()?;
+    fn assignment(&mut self) -> ParserResult<Expression> {
+        let expr = self.ternary()?;
 
-        while match_token!(
-            self,
-            TokenType::GreaterEqual | TokenType::Greater | TokenType::Less | TokenType::LessEqual
-        ) {
-            let operator = match self.previous() {
-                Some(operator) => operator.clone(),
-                None => break,
-            };
-            let right = self.term()?;
+        if match_token!(self, TokenType::Equal) {
+            let equals = self.previous().unwrap().clone();
+            let value_expr = self.assignment()?;
 
-            expression = Expression::Binary {
-                left: Box::new(expression),
-                operator,
-                right: Box::new(right),
-            };
+            Self::assign_to(expr, equals, value_expr)
+        } else if match_token!(self, TokenType::AndEqual | TokenType::OrEqual) {
+            let operator = self.previous().unwrap().clone();
+            let value_expr = self.assignment()?;
+
+            let span = expr.span().to(value_expr.span());
+            if let Expression::Get { .. } = expr {
+                return Ok(Expression::LogicalSet {
+                    target: Box::new(expr),
+                    operator,
+                    value: Box::new(value_expr),
+                    span,
+                });
+            }
+
+            /* x ||= y is desugared into x or (x = y), so y is only evaluated when assigning */
+            let left = Box::new(expr.clone());
+            let right = Box::new(Self::assign_to(expr, operator.clone(), value_expr)?);
+            match operator.token_type() {
+                TokenType::AndEqual => Ok(Expression::And { left, right, span }),
+                _ => Ok(Expression::Or { left, right, span }),
+            }
+        } else {
+            Ok(expr)
         }
+    }
 
-        Ok(expression)
+    fn assign_to(target: Expression, token: Token, value: Expression) -> ParserResult<Expression> {
+        let span = target.span().to(value.span());
+        match target {
+            Expression::Var(variable, _) => Ok(Expression::Assignment {
+                name: variable.token.lexeme().into(),
+                value: Box::new(value),
+                token,
+                span,
+            }),
+            Expression::Get {
+                token, expression, ..
+            } => Ok(Expression::Set {
+                name: token.clone(),
+                object: expression,
+                value: Box::new(value),
+                span,
+            }),
+            _ => Err(ParserError::InvalidAssignmentTarget(Box::new(value))),
+        }
     }
 
-    fn term(&mut self) -> ParserResult<Expression> {
-        let mut expression = self.factor()?;
-        while match_token!(self, TokenType::Minus | TokenType::Plus) {
-            let operator = match self.previous() {
-                Some(operator) => operator.clone(),
-                None => break,
-            };
+    fn ternary(&mut self) -> ParserResult<Expression> {
+        let condition = self.binary(0)?;
 
-            let right = self.factor()?;
-            expression = Expression::Binary {
-                left: Box::new(expression),
-                operator,
-                right: Box::new(right),
-            };
+        if !match_token!(self, TokenType::Question) {
+            return Ok(condition);
         }
 
-        Ok(expression)
+        let then_expr = self.expression()?;
+        expect_token!(self, TokenType::Colon, Colon);
+        /* The ternary operator is right associative: a ? b : c ? d : e == a ? b : (c ? d : e) */
+        let else_expr = self.ternary()?;
+
+        Ok(Expression::Ternary {
+            span: condition.span().to(else_expr.span()),
+            condition: Box::new(condition),
+            then_expr: Box::new(then_expr),
+            else_expr: Box::new(else_expr),
+        })
     }
 
-    fn factor(&mut self) -> ParserResult<Expression> {
+    /// Parses a chain of binary operators by precedence climbing, only consuming operators that
+    /// bind at least as tightly as `min_precedence`. See [`binary_operator`] for the table.
+    fn binary(&mut self, min_precedence: u8) -> ParserResult<Expression> {
         let mut expression = self.unary()?;
 
-        while match_token!(self, TokenType::Slash | TokenType::Star) {
-            let operator = match self.previous() {
-                Some(operator) => operator.clone(),
-                None => break,
+        while let Some(next) = self.peek()
+            && let Some((precedence, associativity)) = binary_operator(next.token_type())
+            && precedence >= min_precedence
+        {
+            let operator = self.advance().unwrap().clone();
+            let right = match associativity {
+                Associativity::Left => self.binary(precedence + 1)?,
             };
 
-            let right = self.unary()?;
-            expression = Expression::Binary {
-                left: Box::new(expression),
-                operator,
-                right: Box::new(right),
+            let span = expression.span().to(right.span());
+            let left = Box::new(expression);
+            let right = Box::new(right);
+            expression = match operator.token_type() {
+                TokenType::Or => Expression::Or { left, right, span },
+                TokenType::And => Expression::And { left, right, span },
+                _ => Expression::Binary {
+                    left,
+                    operator,
+                    right,
+                    span,
+                },
             };
         }
 
@@ -505,14 +774,28 @@ impl<'a> Parser<'a> {
     }
 
     fn unary(&mut self) -> ParserResult<Expression> {
-        if match_token!(self, TokenType::Bang | TokenType::Minus) {
+        if match_token!(self, TokenType::Bang | TokenType::Minus | TokenType::Plus) {
             let operator = match self.previous() {
                 Some(operator) => operator.clone(),
                 None => panic!("Expected finding an operator while parsing an unary expression"),
             };
             let right = self.unary()?;
-            return Ok(Expression::Unary(operator, Box::new(right)));
+            let span = Span::from(&operator).to(right.span());
+            return Ok(Expression::Unary(operator, Box::new(right), span));
+        }
+
+        if match_token!(self, TokenType::PlusPlus | TokenType::MinusMinus) {
+            let operator = self.previous().unwrap().clone();
+            let target = self.unary()?;
+            let span = Span::from(&operator).to(target.span());
+            let target = Box::new(self.increment_target(target, &operator)?);
+            return Ok(Expression::Prefix {
+                operator,
+                target,
+                span,
+            });
         }
+
         self.call()
     }
 
@@ -524,29 +807,58 @@ impl<'a> Parser<'a> {
             } else if match_token!(self, TokenType::Dot) {
                 let identifier = expect_identifier!(self);
                 expr = Expression::Get {
+                    span: expr.span().to(Span::from(identifier)),
                     expression: Box::new(expr),
                     token: identifier.clone(),
                 };
+            } else if match_token!(self, TokenType::LeftBracket) {
+                let index = self.expression()?;
+                expect_token!(self, TokenType::RightBracket, RightBracket);
+                let bracket = self.previous().unwrap().clone();
+                expr = Expression::Index {
+                    span: expr.span().to(Span::from(&bracket)),
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                };
             } else {
                 break;
             }
         }
 
+        if match_token!(self, TokenType::PlusPlus | TokenType::MinusMinus) {
+            let operator = self.previous().unwrap().clone();
+            let span = expr.span().to(Span::from(&operator));
+            let target = Box::new(self.increment_target(expr, &operator)?);
+            expr = Expression::Postfix {
+                target,
+                operator,
+                span,
+            };
+        }
+
         Ok(expr)
     }
 
+    /// Validates that the operand of an increment or decrement can be assigned to.
+    fn increment_target(&self, target: Expression, operator: &Token) -> ParserResult<Expression> {
+        match target {
+            Expression::Var(..) | Expression::Get { .. } => Ok(target),
+            _ => Err(ParserError::InvalidIncrementTarget(operator.clone())),
+        }
+    }
+
     fn finish_call(&mut self, expr: Expression) -> ParserResult<Expression> {
         let mut args = Vec::new();
 
         if !check_token!(self, TokenType::RightParen) {
-            args.push(self.expression()?);
+            args.push(self.assignment()?);
 
             while match_token!(self, TokenType::Comma) {
-                args.push(self.expression()?);
+                args.push(self.assignment()?);
 
                 if args.len() >= MAX_ARGS {
-                    eprintln!("{}", ParserError::TooManyArgs(self.peek().unwrap().clone()));
-                    break;
+                    return Err(ParserError::TooManyArgs(self.peek().unwrap().clone()));
                 }
             }
         }
@@ -555,6 +867,7 @@ impl<'a> Parser<'a> {
         let token = self.previous().unwrap().clone();
 
         Ok(Expression::Call {
+            span: expr.span().to(Span::from(&token)),
             callee: Box::new(expr),
             paren: token,
             args,
@@ -562,26 +875,42 @@ impl<'a> Parser<'a> {
     }
 
     fn primary(&mut self) -> ParserResult<Expression> {
-        match self.peek().unwrap().token_type() {
+        let Some(next) = self.peek() else {
+            return Err(ParserError::UnexpectedEnd);
+        };
+        let span = Span::from(next);
+
+        match next.token_type() {
             TokenType::False => {
                 self.advance();
-                Ok(Expression::False)
+                Ok(Expression::False(span))
             }
             TokenType::True => {
                 self.advance();
-                Ok(Expression::True)
+                Ok(Expression::True(span))
             }
             TokenType::Nil => {
                 self.advance();
-                Ok(Expression::Nil)
+                Ok(Expression::Nil(span))
             }
             TokenType::Number(num) => {
-                let expr = Expression::Number(OrderedFloat(**num));
+                let form = if next.lexeme().contains('.') {
+                    NumberForm::Decimal
+                } else {
+                    NumberForm::Plain
+                };
+                let expr = Expression::Number(OrderedFloat(**num), form, span);
                 self.advance();
                 Ok(expr)
             }
             TokenType::String(str) => {
-                let expr = Expression::String(str.clone());
+                let expr = Expression::String(str.clone(), span);
+                self.advance();
+                Ok(expr)
+            }
+            /* Lox has no character type, so character literals become single-character strings */
+            TokenType::Char(c) => {
+                let expr = Expression::String(c.to_string(), span);
                 self.advance();
                 Ok(expr)
             }
@@ -589,12 +918,14 @@ impl<'a> Parser<'a> {
                 self.advance();
                 Ok(Expression::This {
                     keyword: self.previous().unwrap().clone(),
+                    span,
                 })
             }
             TokenType::Super => {
                 self.advance();
                 Ok(Expression::Super {
                     keyword: self.previous().unwrap().clone(),
+                    span,
                 })
             }
             TokenType::Identifier(_) => {
@@ -602,65 +933,667 @@ impl<'a> Parser<'a> {
                     token: self.peek().unwrap().clone(),
                 };
                 self.advance();
-                Ok(Expression::Var(expression))
+                Ok(Expression::Var(expression, span))
+            }
+            TokenType::LeftBrace => {
+                self.advance();
+                self.block_expression()
             }
             TokenType::LeftParen => {
                 self.advance();
 
-                let expression = self.expression()?;
-
+                /* `()` can only be the parameters of an arrow function */
                 if match_token!(self, TokenType::RightParen) {
-                    Ok(Expression::Grouping(Box::new(expression)))
-                } else {
-                    Err(ParserError::FailedMatch(TokenType::RightParen))
+                    expect_token!(self, TokenType::Arrow, Arrow);
+                    return self.lambda(Vec::new(), span);
                 }
+
+                let expression = self.expression()?;
+                expect_token!(self, TokenType::RightParen, RightParen);
+
+                /*
+                 * The parameters of an arrow function look just like a grouping, so they are
+                 * parsed as one and only turned into parameters once the arrow shows up.
+                 */
+                if match_token!(self, TokenType::Arrow) {
+                    let parameters = match expression {
+                        Expression::Comma(expressions, _) => expressions,
+                        expression => vec![expression],
+                    };
+                    let parameters = parameters
+                        .into_iter()
+                        .map(|parameter| match parameter {
+                            Expression::Var(variable, _) => Ok(variable.token),
+                            other => Err(ParserError::InvalidLambdaParameter(Box::new(other))),
+                        })
+                        .collect::<ParserResult<Vec<Token>>>()?;
+                    return self.lambda(parameters, span);
+                }
+
+                let span = span.to(Span::from(self.previous().unwrap()));
+                Ok(Expression::Grouping(Box::new(expression), span))
+            }
+            a => Err(self.failed_match(a.clone())),
+        }
+    }
+
+    /// Parses the body of an arrow function, after the arrow.
+    fn lambda(&mut self, parameters: Vec<Token>, start: Span) -> ParserResult<Expression> {
+        if parameters.len() >= MAX_ARGS {
+            return Err(ParserError::TooManyArgs(self.previous().unwrap().clone()));
+        }
+
+        let keyword = self.previous().unwrap().clone();
+        let expression = self.assignment()?;
+        let span = start.to(expression.span());
+
+        Ok(Expression::Lambda {
+            parameters,
+            body: vec![Statement::Return {
+                keyword,
+                expression: Some(expression),
+            }],
+            span,
+        })
+    }
+
+    /// Parses the contents of a block used as an expression, after the opening brace. The
+    /// block must end with an expression that is not followed by a semicolon.
+    fn block_expression(&mut self) -> ParserResult<Expression> {
+        use TokenType::*;
+
+        let start = Span::from(self.previous().unwrap());
+        let mut statements = Vec::new();
+
+        loop {
+            if let Some(next) = self.peek()
+                && next.token_type().is_statement_start()
+            {
+                statements.push(self.declaration()?);
+                continue;
+            }
+
+            let expression = self.expression()?;
+            if !match_token!(self, Semicolon) {
+                expect_token!(self, RightBrace, RightBrace);
+                let span = start.to(Span::from(self.previous().unwrap()));
+                return Ok(Expression::Block(statements, Box::new(expression), span));
             }
-            a => Err(ParserError::FailedMatch(a.clone())),
+            statements.push(Statement::Expression(expression));
         }
     }
 
+    /// Whether the statement being parsed ends before the next token: a semicolon, or, with
+    /// implicit semicolons, a line break, a closing brace or the end of the source.
+    fn at_statement_end(&self) -> bool {
+        match self.peek() {
+            Some(next) if matches!(next.token_type(), TokenType::Semicolon) => true,
+            _ if !self.implicit_semicolons => false,
+            None => true,
+            Some(next) => {
+                matches!(next.token_type(), TokenType::RightBrace)
+                    || self
+                        .previous()
+                        .is_some_and(|previous| next.line() > previous.line())
+            }
+        }
+    }
+
+    /// Consumes the semicolon that ends a statement, which implicit semicolons allow to be
+    /// missing at the end of a line.
+    fn expect_statement_end(&mut self) -> ParserResult<()> {
+        if !self.at_statement_end() {
+            return Err(self.failed_match(TokenType::Semicolon));
+        }
+        match_token!(self, TokenType::Semicolon);
+        Ok(())
+    }
+
     fn advance(&mut self) -> Option<&Token> {
         if !self.is_at_end() {
-            self.current += 1;
+            self.previous = std::mem::replace(&mut self.next, self.tokens.next());
         }
         self.previous()
     }
 
     fn previous(&self) -> Option<&Token> {
-        if self.current == 0 {
-            None
-        } else {
-            Some(&self.tokens[self.current - 1])
-        }
+        self.previous.as_ref()
     }
     fn is_at_end(&self) -> bool {
-        self.current >= self.tokens.len()
+        self.next.is_none()
     }
 
-    fn peek(&self) -> Option<&Token> {
-        self.tokens.get(self.current)
+    /// Builds the error for a missing `expected` token, pointing at the line of the token found
+    /// instead, or of the last token if the source ended.
+    fn failed_match(&self, expected: TokenType) -> ParserError {
+        ParserError::FailedMatch {
+            expected,
+            line: self.peek().or(self.previous()).map(Token::line),
+        }
     }
 
+    /// Returns the next token without consuming it, or `None` once every token was parsed.
+    pub fn peek(&self) -> Option<&Token> {
+        self.next.as_ref()
+    }
+
+    /// Skips tokens until the start of the next statement, after a parse error. The token
+    /// that caused the error has not been consumed, and it may itself start the next statement.
     fn synchronize(&mut self) {
-        use TokenType::*;
+        while let Some(next) = self.peek() {
+            if next.token_type().is_statement_start() {
+                return;
+            }
 
-        self.advance();
+            self.advance();
+            if let Some(token) = self.previous()
+                && matches!(token.token_type(), TokenType::Semicolon)
+            {
+                return;
+            }
+        }
+    }
+}
 
-        while !self.is_at_end() {
-            if let Some(token) = self.previous() {
-                if matches!(token.token_type(), Semicolon) {
-                    return;
-                }
+#[cfg(test)]
+mod tests {
+    use crate::parser::{MAX_ARGS, ParserError};
+    use crate::token::TokenType;
+    use crate::{Expression, Parser, Scanner, Span, Statement};
+    use std::io::Cursor;
+
+    fn parse_expression(source: &str) -> Expression {
+        let tokens = Scanner::new(Cursor::new(source)).scan_tokens().unwrap();
+        let mut statements = Parser::new(&tokens).statements().unwrap();
+
+        match statements.remove(0) {
+            Statement::Expression(expression) => expression,
+            other => panic!("Expected an expression statement, found {other:?}"),
+        }
+    }
+
+    fn parse_error(source: &str) -> ParserError {
+        let tokens = Scanner::new(Cursor::new(source)).scan_tokens().unwrap();
+        Parser::new(&tokens).statements().unwrap_err()
+    }
+
+    #[test]
+    fn postfix_increment() {
+        assert_eq!(format!("{:?}", parse_expression("i++;")), "(Var(i) ++)");
+        assert_eq!(
+            format!("{:?}", parse_expression("a.b--;")),
+            "(get(expr: Var(a), name: b) --)"
+        );
+    }
+
+    #[test]
+    fn unary_plus() {
+        assert_eq!(format!("{:?}", parse_expression("+5;")), "(+ 5)");
+        assert_eq!(
+            format!("{:?}", parse_expression("1 + +x;")),
+            "(+ 1 (+ Var(x)))"
+        );
+    }
+
+    #[test]
+    fn prefix_increment() {
+        assert_eq!(format!("{:?}", parse_expression("++i;")), "(++ Var(i))");
+        assert_eq!(
+            format!("{:?}", parse_expression("-++i;")),
+            "(- (++ Var(i)))"
+        );
+        assert!(matches!(
+            parse_error("++1;"),
+            ParserError::InvalidIncrementTarget(_)
+        ));
+        assert!(matches!(
+            parse_error("++i++;"),
+            ParserError::InvalidIncrementTarget(_)
+        ));
+    }
+
+    #[test]
+    fn too_many_arguments() {
+        let args = vec!["x"; MAX_ARGS + 1].join(", ");
+        assert!(matches!(
+            parse_error(&format!("f({args});")),
+            ParserError::TooManyArgs(_)
+        ));
+        assert!(matches!(
+            parse_error(&format!("fun f({args}) {{}}")),
+            ParserError::TooManyArgs(_)
+        ));
+    }
+
+    #[test]
+    fn postfix_increment_needs_assignable_target() {
+        assert!(matches!(
+            parse_error("1++;"),
+            ParserError::InvalidIncrementTarget(_)
+        ));
+        assert!(matches!(
+            parse_error("f()--;"),
+            ParserError::InvalidIncrementTarget(_)
+        ));
+    }
+
+    #[test]
+    fn logical_assignment() {
+        assert_eq!(
+            format!("{:?}", parse_expression("x ||= 1;")),
+            "(Var(x)) || (Assign(name = 1))"
+        );
+        assert_eq!(
+            format!("{:?}", parse_expression("f().b &&= c;")),
+            "(&&= get(expr: call (callee: Var(f), args: []), name: b) Var(c))"
+        );
+        assert!(matches!(
+            parse_error("1 ||= 2;"),
+            ParserError::InvalidAssignmentTarget(_)
+        ));
+    }
+
+    #[test]
+    fn comma_operator() {
+        assert_eq!(
+            format!("{:?}", parse_expression("(a, b = 2, c);")),
+            "(group (, Var(a) Assign(name = 2) Var(c)))"
+        );
+        assert_eq!(
+            format!("{:?}", parse_expression("f(a, b);")),
+            "call (callee: Var(f), args: [Var(a), Var(b)])"
+        );
+        assert_eq!(
+            format!("{:?}", parse_expression("f((a, b));")),
+            "call (callee: Var(f), args: [(group (, Var(a) Var(b)))])"
+        );
+    }
+
+    #[test]
+    fn expression_spans() {
+        let span = |start, end, start_offset, end_offset| Span {
+            start,
+            end,
+            start_offset,
+            end_offset,
+        };
+        let expression = parse_expression("1 +\n  (2 *\n  3);");
+        assert_eq!(expression.span(), span(1, 3, 0, 15));
+
+        let Expression::Binary { left, right, .. } = expression else {
+            panic!("Expected a binary expression");
+        };
+        assert_eq!(left.span(), span(1, 1, 0, 1));
+        assert_eq!(right.span(), span(2, 3, 6, 15));
+    }
+
+    #[test]
+    fn nested_property_assignment() {
+        let Expression::Set {
+            name,
+            object,
+            value,
+            ..
+        } = parse_expression("a.b.c = v;")
+        else {
+            panic!("Expected a set expression");
+        };
+
+        assert_eq!(name.lexeme(), "c");
+        assert_eq!(format!("{object:?}"), "get(expr: Var(a), name: b)");
+        assert_eq!(format!("{value:?}"), "Var(v)");
+    }
+
+    #[test]
+    fn subscript() {
+        assert_eq!(
+            format!("{:?}", parse_expression("a.b[i + 1];")),
+            "index(expr: get(expr: Var(a), name: b), index: (+ Var(i) 1))"
+        );
+        assert_eq!(
+            format!("{:?}", parse_expression("f()[0][1];")),
+            "index(expr: index(expr: call (callee: Var(f), args: []), index: 0), index: 1)"
+        );
+        assert!(matches!(
+            parse_error("a[0;"),
+            ParserError::FailedMatch {
+                expected: TokenType::RightBracket,
+                ..
             }
+        ));
+        assert!(matches!(
+            parse_error("a[0] = 1;"),
+            ParserError::InvalidAssignmentTarget(_)
+        ));
+    }
 
-            let next = self.peek().unwrap().token_type();
-            if matches!(
-                next,
-                Class | Fun | Var | For | If | While | Print | Return | Continue
-            ) {
-                return;
+    #[test]
+    fn block_expression() {
+        let tokens = Scanner::new(Cursor::new("var x = { var t = 2; t; { t * 2 } };"))
+            .scan_tokens()
+            .unwrap();
+        let statements = Parser::new(&tokens).statements().unwrap();
+
+        let Statement::VariableDeclaration {
+            initializer: Some(initializer),
+            ..
+        } = &statements[0]
+        else {
+            panic!("Expected a variable declaration");
+        };
+        assert_eq!(
+            format!("{initializer:?}"),
+            "(block var t = 2; Var(t); (block (* Var(t) 2)))"
+        );
+    }
+
+    #[test]
+    fn block_expression_requires_trailing_expression() {
+        assert!(matches!(
+            parse_error("var x = { 1; };"),
+            ParserError::FailedMatch { .. }
+        ));
+    }
+
+    #[test]
+    fn ternary_expression() {
+        let expression = parse_expression("a ? 1 : 2;");
+        assert_eq!(format!("{expression:?}"), "(?: Var(a) 1 2)");
+    }
+
+    #[test]
+    fn nested_ternary_is_right_associative() {
+        let expression = parse_expression("a ? 1 : b ? 2 : 3;");
+        assert_eq!(format!("{expression:?}"), "(?: Var(a) 1 (?: Var(b) 2 3))");
+    }
+
+    #[test]
+    fn ternary_binds_looser_than_or() {
+        let expression = parse_expression("a or b ? x = 1 : 2;");
+        assert_eq!(
+            format!("{expression:?}"),
+            "(?: (Var(a)) || (Var(b)) Assign(name = 1) 2)"
+        );
+    }
+
+    #[test]
+    fn binary_operator_precedence() {
+        assert_eq!(
+            format!("{:?}", parse_expression("1 + 2 * 3 - 4;")),
+            "(- (+ 1 (* 2 3)) 4)"
+        );
+        assert_eq!(
+            format!("{:?}", parse_expression("a < b == c >= d;")),
+            "(== (< Var(a) Var(b)) (>= Var(c) Var(d)))"
+        );
+        assert_eq!(
+            format!("{:?}", parse_expression("a or b and c == d;")),
+            "(Var(a)) || ((Var(b)) && ((== Var(c) Var(d))))"
+        );
+        assert_eq!(
+            format!("{:?}", parse_expression("8 / 4 / 2;")),
+            "(/ (/ 8 4) 2)"
+        );
+    }
+
+    #[test]
+    fn parse_from_token_stream() {
+        let scanner = Scanner::new(Cursor::new("var x = 1 + 2;\nprint x;"));
+        let statements = Parser::from_tokens(scanner.map(Result::unwrap))
+            .statements()
+            .unwrap();
+
+        let statements: Vec<String> = statements.iter().map(Statement::to_string).collect();
+        assert_eq!(statements, ["var x = (+ 1 2);", "print Var(x);"]);
+    }
+
+    #[test]
+    fn arrow_functions() {
+        assert_eq!(
+            format!("{:?}", parse_expression("() => 1;")),
+            "(=> () return 1;)"
+        );
+        assert_eq!(
+            format!("{:?}", parse_expression("(a) => a + 1;")),
+            "(=> (a) return (+ Var(a) 1);)"
+        );
+        assert_eq!(
+            format!("{:?}", parse_expression("f((a, b) => a * b, (c));")),
+            "call (callee: Var(f), args: [(=> (a, b) return (* Var(a) Var(b));), (group Var(c))])"
+        );
+        assert!(matches!(
+            parse_error("(a, 1) => a;"),
+            ParserError::InvalidLambdaParameter(_)
+        ));
+    }
+
+    #[test]
+    fn print_multiple_values() {
+        let tokens = Scanner::new(Cursor::new("print a;\nprint a, (b, c), d = 1;"))
+            .scan_tokens()
+            .unwrap();
+        let statements = Parser::new(&tokens).statements().unwrap();
+
+        let statements: Vec<String> = statements.iter().map(Statement::to_string).collect();
+        assert_eq!(
+            statements,
+            [
+                "print Var(a);",
+                "print Var(a), (group (, Var(b) Var(c))), Assign(name = 1);"
+            ]
+        );
+    }
+
+    #[test]
+    fn static_fields() {
+        let tokens = Scanner::new(Cursor::new(
+            "class Counter { static var count = 0; static var label; next() {} static reset() {} }",
+        ))
+        .scan_tokens()
+        .unwrap();
+        let mut statements = Parser::new(&tokens).statements().unwrap();
+
+        let Statement::ClassDeclaration {
+            methods,
+            static_fields,
+            static_methods,
+            ..
+        } = statements.remove(0)
+        else {
+            panic!("Expected a class declaration");
+        };
+        assert_eq!(methods.len(), 1);
+        assert_eq!(static_methods[0].name, "reset");
+        let fields: Vec<_> = static_fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.initializer.is_some()))
+            .collect();
+        assert_eq!(fields, [("count", true), ("label", false)]);
+    }
+
+    #[test]
+    fn loop_else() {
+        let tokens = Scanner::new(Cursor::new(
+            "while (a) {} else print 1;\nfor (;;) {}\nif (a) while (b) {} else print 2;\n\
+             if (a) { while (b) {} else print 3; }",
+        ))
+        .scan_tokens()
+        .unwrap();
+        let statements = Parser::new(&tokens).statements().unwrap();
+
+        assert!(matches!(
+            &statements[0],
+            Statement::While {
+                else_branch: Some(_),
+                ..
             }
-            self.advance();
+        ));
+        assert!(matches!(
+            &statements[1],
+            Statement::For {
+                else_branch: None,
+                ..
+            }
+        ));
+        let Statement::If {
+            then_branch,
+            else_branch: Some(_),
+            ..
+        } = &statements[2]
+        else {
+            panic!("The else should belong to the if");
+        };
+        assert!(matches!(
+            **then_branch,
+            Statement::While {
+                else_branch: None,
+                ..
+            }
+        ));
+        let Statement::If {
+            else_branch: None, ..
+        } = &statements[3]
+        else {
+            panic!("A braced loop should keep its else");
+        };
+    }
+
+    #[test]
+    fn expression_cut_short_by_the_end_of_the_source() {
+        assert!(matches!(
+            parse_error("print 1 +"),
+            ParserError::UnexpectedEnd
+        ));
+        assert!(matches!(parse_error("var x ="), ParserError::UnexpectedEnd));
+        assert!(matches!(parse_error("var"), ParserError::UnexpectedEnd));
+        assert!(matches!(parse_error("if (1)"), ParserError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn block_cut_short_by_the_end_of_the_source() {
+        for source in ["{", "{ print 1;", "with (1) {", "try {", "fun f() {"] {
+            assert!(
+                matches!(parse_error(source), ParserError::UnexpectedEnd),
+                "{source}"
+            );
         }
     }
+
+    #[test]
+    fn synchronize_keeps_the_statement_after_an_error() {
+        let source = "var a = var b = 2;\nvar = 3; var c = 4;";
+        let tokens = Scanner::new(Cursor::new(source)).scan_tokens().unwrap();
+        let mut parser = Parser::new(&tokens);
+
+        let mut names = Vec::new();
+        while !parser.is_at_end() {
+            if let Ok(Statement::VariableDeclaration { name, .. }) = parser.declaration() {
+                names.push(name);
+            }
+        }
+
+        assert_eq!(names, ["b", "c"]);
+    }
+
+    #[test]
+    fn with_statement() {
+        let tokens = Scanner::new(Cursor::new("with (open(path)) { print 1; }"))
+            .scan_tokens()
+            .unwrap();
+        let statements = Parser::new(&tokens).statements().unwrap();
+
+        let Statement::With { resource, body, .. } = &statements[0] else {
+            panic!("Expected a with statement, found {:?}", statements[0]);
+        };
+        assert!(matches!(resource, Expression::Call { .. }));
+        assert_eq!(body.len(), 1);
+
+        assert!(matches!(
+            parse_error("with (a) print 1;"),
+            ParserError::FailedMatch { .. }
+        ));
+    }
+
+    #[test]
+    fn implicit_semicolons() {
+        let source =
+            "var a = 1\nprint a,\n  2\na = a +\n  1\nfun f() { return }\nprint a; print f()";
+        let tokens = Scanner::new(Cursor::new(source)).scan_tokens().unwrap();
+
+        let mut parser = Parser::new(&tokens);
+        parser.set_implicit_semicolons(true);
+        let statements = parser.statements().unwrap();
+
+        let statements: Vec<String> = statements.iter().map(Statement::to_string).collect();
+        assert_eq!(
+            statements,
+            [
+                "var a = 1;",
+                "print Var(a), 2;",
+                "Assign(name = (+ Var(a) 1));",
+                "fun f()",
+                "print Var(a);",
+                "print call (callee: Var(f), args: []);",
+            ]
+        );
+    }
+
+    #[test]
+    fn semicolons_are_required_by_default() {
+        assert!(matches!(
+            parse_error("var a = 1\nprint a;"),
+            ParserError::FailedMatch {
+                expected: TokenType::Semicolon,
+                line: Some(2)
+            }
+        ));
+
+        let tokens = Scanner::new(Cursor::new("print 1 print 2;"))
+            .scan_tokens()
+            .unwrap();
+        let mut parser = Parser::new(&tokens);
+        parser.set_implicit_semicolons(true);
+        assert!(parser.statements().is_err());
+    }
+
+    #[test]
+    fn statements_know_their_line() {
+        let tokens = Scanner::new(Cursor::new(
+            "var a;\nif (a)\n  {}\nfor (;;) {}\nclass A {}\nfun f() {}\ntry {} catch (e) {}",
+        ))
+        .scan_tokens()
+        .unwrap();
+        let statements = Parser::new(&tokens).statements().unwrap();
+
+        let lines: Vec<usize> = statements.iter().map(Statement::line).collect();
+        assert_eq!(lines, [1, 2, 4, 5, 6, 7]);
+        let Statement::If { then_branch, .. } = &statements[1] else {
+            panic!("Expected an if statement");
+        };
+        assert_eq!(then_branch.line(), 3);
+    }
+
+    #[test]
+    fn match_patterns() {
+        let tokens = Scanner::new(Cursor::new(
+            "match x { 1 => {} \"a\" => {}, _ => print 0; }",
+        ))
+        .scan_tokens()
+        .unwrap();
+        let statements = Parser::new(&tokens).statements().unwrap();
+
+        let Statement::Match { arms, .. } = &statements[0] else {
+            panic!("Expected a match statement");
+        };
+        let patterns: Vec<String> = arms
+            .iter()
+            .map(|arm| format!("{:?}", arm.pattern))
+            .collect();
+        assert_eq!(patterns, ["Some(1)", "Some(a)", "None"]);
+
+        assert!(matches!(
+            parse_error("match x { y => {} }"),
+            ParserError::InvalidPattern(_)
+        ));
+    }
 }
@@ -3,16 +3,25 @@ pub mod error;
 use crate::token::*;
 use std::collections::HashMap;
 use std::io::BufRead;
+use std::ops::Range;
 
 static DECIMAL_SEPARATOR: u8 = b'.';
 
 pub type ScannerResult<T> = Result<T, error::ScannerError>;
 
+/// Turns source code into tokens. Besides [`Scanner::scan_tokens`], the scanner is an
+/// [`Iterator`] that reads and yields one token at a time, which can be handed to
+/// [`Parser::from_tokens`](crate::Parser::from_tokens) to parse without buffering the source.
 pub struct Scanner<R: BufRead> {
     reader: R,
     line: usize,
     current_byte: Option<u8>,
+    /// The byte offset of `current_byte` in the source.
+    offset: usize,
+    /// The byte offset of the token being scanned.
+    token_start: usize,
     identifier_map: HashMap<String, TokenType>,
+    line_recovery: bool,
 
     started: bool,
 }
@@ -25,6 +34,12 @@ enum NumberParseSection {
 
 impl<R: BufRead> Scanner<R> {
     pub fn new(reader: R) -> Self {
+        Self::new_with_line(reader, 1)
+    }
+
+    /// Creates a scanner whose first line is numbered `start_line`, for sources that continue
+    /// an earlier one.
+    pub fn new_with_line(reader: R, start_line: usize) -> Self {
         let mut identifier_map = HashMap::new();
         macro_rules! insert_token {
             ($str: expr, $tkn: ident) => {
@@ -45,6 +60,14 @@ impl<R: BufRead> Scanner<R> {
         insert_token!("return", Return);
         insert_token!("break", Break);
         insert_token!("continue", Continue);
+        insert_token!("throw", Throw);
+        insert_token!("try", Try);
+        insert_token!("catch", Catch);
+        insert_token!("finally", Finally);
+        insert_token!("static", Static);
+        insert_token!("with", With);
+        insert_token!("match", Match);
+        insert_token!("_", Underscore);
         insert_token!("super", Super);
         insert_token!("this", This);
         insert_token!("true", True);
@@ -53,13 +76,23 @@ impl<R: BufRead> Scanner<R> {
 
         Scanner {
             reader,
-            line: 1,
+            line: start_line,
             current_byte: None,
+            offset: 0,
+            token_start: 0,
             identifier_map,
+            line_recovery: false,
             started: false,
         }
     }
 
+    /// When enabled, an unknown byte makes the scanner skip the rest of the line it appears in.
+    /// The error is still yielded, but scanning resumes on the next line, so a single stray
+    /// character does not cascade into more errors and later lines can still be checked.
+    pub fn set_line_recovery(&mut self, line_recovery: bool) {
+        self.line_recovery = line_recovery;
+    }
+
     fn scan_token(&mut self) -> Option<ScannerResult<Token>> {
         use TokenType::*;
 
@@ -85,26 +118,48 @@ impl<R: BufRead> Scanner<R> {
         }
 
         let current = self.consume_whitespace()?;
+        self.token_start = self.offset - 1;
         let token = match current {
             b'(' => add_single_byte!(current, LeftParen),
             b')' => add_single_byte!(current, RightParen),
             b'{' => add_single_byte!(current, LeftBrace),
             b'}' => add_single_byte!(current, RightBrace),
+            b'[' => add_single_byte!(current, LeftBracket),
+            b']' => add_single_byte!(current, RightBracket),
             b',' => add_single_byte!(current, Comma),
             b'.' => add_single_byte!(current, Dot),
-            b'-' => add_single_byte!(current, Minus),
-            b'+' => add_single_byte!(current, Plus),
+            b'-' => add_multiple_if_match!(current, b'-', MinusMinus, Minus),
+            b'+' => add_multiple_if_match!(current, b'+', PlusPlus, Plus),
             b';' => add_single_byte!(current, Semicolon),
             b'*' => add_single_byte!(current, Star),
+            b'?' => add_single_byte!(current, Question),
+            b':' => add_single_byte!(current, Colon),
             b'!' => add_multiple_if_match!(current, b'=', BangEqual, Bang),
+            b'=' if self.current_byte == Some(b'>') => {
+                lexeme.extend([current, b'>']);
+                self.advance();
+                self.add_token(Arrow, lexeme)
+            }
             b'=' => add_multiple_if_match!(current, b'=', EqualEqual, Equal),
             b'<' => add_multiple_if_match!(current, b'=', LessEqual, Less),
             b'>' => add_multiple_if_match!(current, b'=', GreaterEqual, Greater),
             b'/' => add_single_byte!(current, Slash),
+            b'&' => {
+                lexeme.push(current);
+                self.consume_logical_assignment(lexeme, AndEqual)
+            }
+            b'|' => {
+                lexeme.push(current);
+                self.consume_logical_assignment(lexeme, OrEqual)
+            }
             b'"' => {
                 lexeme.push(current);
                 self.consume_string(lexeme)
             }
+            b'\'' => {
+                lexeme.push(current);
+                self.consume_char(lexeme)
+            }
             b'0'..=b'9' => {
                 lexeme.push(current);
                 self.consume_number(lexeme)
@@ -113,10 +168,16 @@ impl<R: BufRead> Scanner<R> {
                 lexeme.push(current);
                 self.consume_identifier(lexeme)
             }
-            a => Err(error::ScannerError {
-                error_type: error::ErrorType::UnknownByte(a),
-                line: self.line,
-            }),
+            a => {
+                let error = error::ScannerError {
+                    error_type: error::ErrorType::UnknownByte(a),
+                    line: self.line,
+                };
+                if self.line_recovery {
+                    self.skip_line();
+                }
+                Err(error)
+            }
         };
         Some(token)
     }
@@ -132,11 +193,40 @@ impl<R: BufRead> Scanner<R> {
             }
         };
 
-        Ok(Token::new(token_type, lexeme, self.line))
+        Ok(Token::new_with_offset(
+            token_type,
+            lexeme,
+            self.line,
+            self.token_start,
+        ))
+    }
+
+    /// Consumes the rest of a `&&=` or `||=` operator, whose first byte is already in `lexeme`.
+    /// The bytes cannot appear on their own, so anything else is reported as an unknown byte.
+    fn consume_logical_assignment(
+        &mut self,
+        mut lexeme: Vec<u8>,
+        token_type: TokenType,
+    ) -> ScannerResult<Token> {
+        let first = lexeme[0];
+        if self.match_character(first) && self.match_character(b'=') {
+            lexeme.extend([first, b'=']);
+            return self.add_token(token_type, lexeme);
+        }
+
+        let error = error::ScannerError {
+            error_type: error::ErrorType::UnknownByte(first),
+            line: self.line,
+        };
+        if self.line_recovery {
+            self.skip_line();
+        }
+        Err(error)
     }
 
     fn consume_string(&mut self, mut lexeme: Vec<u8>) -> ScannerResult<Token> {
         let mut completed = false;
+        let mut escaped = false;
         while let Some(c) = self.current_byte {
             match c {
                 b'\n' => {
@@ -144,7 +234,7 @@ impl<R: BufRead> Scanner<R> {
                     lexeme.push(c);
                     self.advance();
                 }
-                b'"' => {
+                b'"' if !escaped => {
                     completed = true;
                     lexeme.push(c);
                     break;
@@ -154,6 +244,7 @@ impl<R: BufRead> Scanner<R> {
                     self.advance();
                 }
             }
+            escaped = c == b'\\' && !escaped;
         }
 
         self.advance();
@@ -167,10 +258,61 @@ impl<R: BufRead> Scanner<R> {
 
         let string = &lexeme[1..lexeme.len() - 1];
         let string = crate::utf8::convert_byte_slice_into_utf8(string);
+        let string = self.unescape(&string)?;
 
         self.add_token(TokenType::String(string), lexeme)
     }
 
+    /// Consumes a character literal, which must contain exactly one character (or one escape
+    /// sequence) between single quotes.
+    fn consume_char(&mut self, mut lexeme: Vec<u8>) -> ScannerResult<Token> {
+        let mut completed = false;
+        let mut escaped = false;
+        while let Some(c) = self.current_byte {
+            if c == b'\n' {
+                break;
+            }
+
+            lexeme.push(c);
+            self.advance();
+
+            if c == b'\'' && !escaped {
+                completed = true;
+                break;
+            }
+            escaped = c == b'\\' && !escaped;
+        }
+
+        if !completed {
+            return Err(error::ScannerError {
+                error_type: error::ErrorType::UnterminatedCharLiteral,
+                line: self.line,
+            });
+        }
+
+        let content = crate::utf8::convert_byte_slice_into_utf8(&lexeme[1..lexeme.len() - 1]);
+        let content = self.unescape(&content)?;
+
+        let mut chars = content.chars();
+        let error_type = match (chars.next(), chars.next()) {
+            (Some(c), None) => return self.add_token(TokenType::Char(c), lexeme),
+            (None, _) => error::ErrorType::EmptyCharLiteral,
+            (Some(_), Some(_)) => error::ErrorType::OverlongCharLiteral,
+        };
+
+        Err(error::ScannerError {
+            error_type,
+            line: self.line,
+        })
+    }
+
+    fn unescape(&self, raw: &str) -> ScannerResult<String> {
+        crate::escape::unescape(raw).map_err(|error_type| error::ScannerError {
+            error_type,
+            line: self.line,
+        })
+    }
+
     fn consume_number(&mut self, mut lexeme: Vec<u8>) -> ScannerResult<Token> {
         // Parse the first digit.
         let mut decimal: f64 = (lexeme[0] - 0x30) as f64;
@@ -179,7 +321,9 @@ impl<R: BufRead> Scanner<R> {
 
         while let Some(c) = self.current_byte {
             if c == DECIMAL_SEPARATOR {
-                if current_part == NumberParseSection::Decimal {
+                /* A separator not followed by a digit is a property access, as in `5.method()` */
+                let followed_by_digit = self.peek_next().is_some_and(|n| n.is_ascii_digit());
+                if current_part == NumberParseSection::Decimal || !followed_by_digit {
                     break;
                 }
                 current_part = NumberParseSection::Decimal;
@@ -246,12 +390,7 @@ impl<R: BufRead> Scanner<R> {
                     if !self.match_character(b'/') {
                         break Some(current);
                     }
-                    while let Some(current) = self.current_byte {
-                        if current == b'\n' {
-                            break;
-                        }
-                        self.advance();
-                    }
+                    self.skip_line();
                 }
 
                 _ => break Some(current),
@@ -259,6 +398,16 @@ impl<R: BufRead> Scanner<R> {
         }
     }
 
+    /// Advances until the end of the current line. The line break itself is not consumed.
+    fn skip_line(&mut self) {
+        while let Some(current) = self.current_byte {
+            if current == b'\n' {
+                break;
+            }
+            self.advance();
+        }
+    }
+
     fn match_character(&mut self, other: u8) -> bool {
         let current = match self.current_byte {
             Some(current) => current,
@@ -273,9 +422,14 @@ impl<R: BufRead> Scanner<R> {
         }
     }
 
+    /// Returns the byte after the current one, without consuming anything.
+    fn peek_next(&mut self) -> Option<u8> {
+        self.reader.fill_buf().ok()?.first().copied()
+    }
+
     fn advance(&mut self) -> Option<u8> {
         let mut buf = [0u8; 1];
-        match self.reader.read_exact(&mut buf) {
+        let current_byte = match self.reader.read_exact(&mut buf) {
             Ok(_) => {
                 let current_byte = self.current_byte.take();
 
@@ -288,7 +442,11 @@ impl<R: BufRead> Scanner<R> {
              * we have one single byte remaining on the scanner, which would be the current byte
              */
             Err(_) => self.current_byte.take(),
+        };
+        if current_byte.is_some() {
+            self.offset += 1;
         }
+        current_byte
     }
     pub fn scan_tokens(self) -> ScannerResult<Vec<Token>> {
         let mut tokens = Vec::new();
@@ -322,10 +480,133 @@ impl<R: BufRead> Iterator for Scanner<R> {
 
 impl<R: BufRead> std::iter::FusedIterator for Scanner<R> {}
 
+impl<'a> Scanner<&'a [u8]> {
+    /// Updates `prev`, the tokens of a source before an edit, to the edited `source`. `range` is
+    /// the byte range of the edited text in `source`, and `line_delta` the number of lines the
+    /// edit added, or removed if negative. Only the lines from the edit onward are scanned again,
+    /// starting earlier if a multi-line string crosses into the edited line. As soon as a token
+    /// past the edit begins a line just like the previous tokens did, the rest of `prev` is
+    /// reused with its lines shifted. Lines are expected to be separated by `\n`.
+    ///
+    /// The text the edit replaced is no longer in `source`, and `prev` does not record where
+    /// the old lines ended, so the number of lines the edit removed cannot be worked out here.
+    /// That is why the caller, which knows the replaced text, passes `line_delta`: it tells which
+    /// token of `prev` the scan has caught up with, and how far to shift the reused ones.
+    ///
+    /// A `range` that does not fit in `source`, or a `line_delta` larger than the lines the edit
+    /// spans or that shifts a token out of the source, is an error.
+    pub fn rescan_range(
+        prev: &[Token],
+        source: &'a str,
+        range: Range<usize>,
+        line_delta: isize,
+    ) -> ScannerResult<Vec<Token>> {
+        let bytes = source.as_bytes();
+        let line_of = |offset: usize| 1 + bytes[..offset].iter().filter(|&&b| b == b'\n').count();
+        if range.start > range.end || range.end > bytes.len() {
+            return Err(error::ScannerError {
+                line: line_of(range.start.min(bytes.len())),
+                error_type: error::ErrorType::InvalidEditRange(range),
+            });
+        }
+        let edit_start = line_of(range.start);
+        let edit_end = line_of(range.end);
+
+        /* The edited text holds every line the edit added */
+        let invalid_delta = |line| error::ScannerError {
+            error_type: error::ErrorType::InvalidLineDelta(line_delta),
+            line,
+        };
+        if line_delta > (edit_end - edit_start) as isize {
+            return Err(invalid_delta(edit_start));
+        }
+        let last_line = line_of(bytes.len());
+
+        /* A token that ends in the edited line might have started before it */
+        let start_line = prev
+            .iter()
+            .find(|token| token.line() >= edit_start)
+            .map_or(edit_start, |token| edit_start.min(first_line(token)));
+        let first = prev
+            .iter()
+            .position(|token| token.line() >= start_line)
+            .unwrap_or(prev.len());
+
+        let offset = match start_line {
+            1 => 0,
+            _ => bytes
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| **b == b'\n')
+                .nth(start_line - 2)
+                .map_or(bytes.len(), |(i, _)| i + 1),
+        };
+        let mut scanner = Scanner::new(&bytes[offset..]);
+        scanner.line = start_line;
+        scanner.offset = offset;
+
+        let mut tokens = prev[..first].to_vec();
+        for token in scanner {
+            let token = token?;
+
+            /*
+             * The text after the edit did not change, so once a token past it starts a line in
+             * both scans, the scanner was in the same state and would yield the same tokens.
+             */
+            let starts_line = tokens.last().is_none_or(|last| begins_line(last, &token));
+            if first_line(&token) > edit_end
+                && starts_line
+                && let Some(reused) = resync(&prev[first..], &token, line_delta)
+            {
+                for old in reused {
+                    let line = old
+                        .line()
+                        .checked_add_signed(line_delta)
+                        .filter(|line| (1..=last_line).contains(line))
+                        .ok_or_else(|| invalid_delta(old.line()))?;
+                    tokens.push(Token::new_with_offset(
+                        old.token_type().clone(),
+                        old.lexeme().to_string(),
+                        line,
+                        token.offset() + (old.offset() - reused[0].offset()),
+                    ));
+                }
+                return Ok(tokens);
+            }
+
+            tokens.push(token);
+        }
+
+        Ok(tokens)
+    }
+}
+
+/// Returns the line a token starts in, since the line of a multi-line string is the one it ends in.
+fn first_line(token: &Token) -> usize {
+    token.line() - token.lexeme().matches('\n').count()
+}
+
+fn begins_line(previous: &Token, token: &Token) -> bool {
+    previous.line() < first_line(token)
+}
+
+/// Finds the token of `prev` that `token` was before the edit moved it by `line_delta` lines.
+/// It only counts if it also began its line. Returns it along with the tokens after it.
+fn resync<'t>(prev: &'t [Token], token: &Token, line_delta: isize) -> Option<&'t [Token]> {
+    let line = first_line(token).checked_add_signed(-line_delta)?;
+    let j = prev.iter().position(|old| first_line(old) >= line)?;
+
+    let old = &prev[j];
+    let begins = j == 0 || begins_line(&prev[j - 1], old);
+    let same = old.token_type() == token.token_type() && old.lexeme() == token.lexeme();
+    (first_line(old) == line && begins && same).then_some(&prev[j..])
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::Token;
+    use crate::scanner::error::ErrorType;
     use crate::token::TokenType;
+    use crate::{ScannerResult, Token};
     use std::io::Cursor;
 
     macro_rules! semicolon_token {
@@ -344,13 +625,21 @@ mod tests {
         }};
     }
 
+    /// Clears the offsets of the tokens, so they can be compared with tokens written by hand.
+    fn without_offsets(tokens: Vec<Token>) -> Vec<Token> {
+        tokens
+            .into_iter()
+            .map(|t| Token::new(t.token_type().clone(), t.lexeme().to_string(), t.line()))
+            .collect()
+    }
+
     #[test]
     fn single_byte_tokens() {
         let source = "   =/+-    (){}   ;   // this is a comment that should be ignored.\n = +";
         let scanner = super::Scanner::new(Cursor::new(source));
         let result = scanner.scan_tokens().unwrap();
         assert_eq!(
-            result,
+            without_offsets(result),
             [
                 Token::new(TokenType::Equal, String::from("="), 1),
                 Token::new(TokenType::Slash, String::from("/"), 1),
@@ -374,7 +663,7 @@ mod tests {
         let result: Vec<Token> = scanner.scan_tokens().unwrap();
 
         assert_eq!(
-            result,
+            without_offsets(result),
             [
                 Token::new(TokenType::Equal, String::from("="), 1),
                 Token::new(
@@ -392,7 +681,7 @@ mod tests {
         let scanner = super::Scanner::new(Cursor::new(source));
         let result: Vec<Token> = scanner.map(|i| i.unwrap()).collect();
         assert_eq!(
-            result,
+            without_offsets(result),
             [
                 Token::new(TokenType::Equal, String::from("="), 1),
                 Token::new(
@@ -404,6 +693,24 @@ mod tests {
         )
     }
 
+    #[test]
+    fn logical_assignment_operators() {
+        let source = "&&= ||=";
+        let scanner = super::Scanner::new(Cursor::new(source));
+        let result: Vec<Token> = scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            without_offsets(result),
+            [
+                Token::new(TokenType::AndEqual, String::from("&&="), 1),
+                Token::new(TokenType::OrEqual, String::from("||="), 1),
+            ]
+        );
+
+        let mut scanner = super::Scanner::new(Cursor::new("a && b"));
+        assert!(scanner.nth(1).unwrap().is_err());
+    }
+
     #[test]
     fn test_number_parsing() {
         let source = "    30.5    ;    ";
@@ -411,7 +718,7 @@ mod tests {
         let result: Vec<Token> = scanner.scan_tokens().unwrap();
 
         assert_eq!(
-            result,
+            without_offsets(result),
             [
                 Token::new(
                     TokenType::Number(ordered_float::OrderedFloat(30.5)),
@@ -423,6 +730,26 @@ mod tests {
         )
     }
 
+    #[test]
+    fn decimal_separator_needs_a_digit() {
+        let types = |source: &str| -> Vec<TokenType> {
+            scan(source)
+                .into_iter()
+                .map(|t| t.token_type().clone())
+                .collect()
+        };
+        let number = |n: f64| TokenType::Number(ordered_float::OrderedFloat(n));
+        let method = TokenType::Identifier(String::from("method"));
+
+        assert_eq!(types("5.0"), [number(5.0)]);
+        assert_eq!(
+            types("5.method"),
+            [number(5.0), TokenType::Dot, method.clone()]
+        );
+        assert_eq!(types("5.25.method"), [number(5.25), TokenType::Dot, method]);
+        assert_eq!(types("5."), [number(5.0), TokenType::Dot]);
+    }
+
     #[test]
     fn test_identifiers() {
         let source = "print\nfoo\nand or bar // sample\nbreak\nfun\nsuper\ncontinue return while";
@@ -430,7 +757,7 @@ mod tests {
         let result = scanner.scan_tokens().unwrap();
 
         assert_eq!(
-            result,
+            without_offsets(result),
             [
                 Token::new(TokenType::Print, String::from("print"), 1),
                 Token::new(
@@ -462,7 +789,7 @@ mod tests {
         let result = scanner.scan_tokens().unwrap();
 
         assert_eq!(
-            result,
+            without_offsets(result),
             [
                 Token::new(
                     TokenType::Identifier(String::from("andor")),
@@ -494,7 +821,7 @@ mod tests {
         let scanner = super::Scanner::new(Cursor::new(source));
         let result: Vec<Token> = scanner.map(|i| i.unwrap()).collect();
         assert_eq!(
-            result,
+            without_offsets(result),
             [
                 Token::new(TokenType::EqualEqual, String::from("=="), 1),
                 Token::new(TokenType::GreaterEqual, String::from(">="), 1),
@@ -511,7 +838,7 @@ mod tests {
         let result: Vec<Token> = scanner.map(|i| i.unwrap()).collect();
 
         assert_eq!(
-            result,
+            without_offsets(result),
             [
                 Token::new(TokenType::Equal, String::from("="), 1,),
                 Token::new(
@@ -532,7 +859,7 @@ mod tests {
         let result: Vec<Token> = scanner.map(|i| i.unwrap()).collect();
 
         assert_eq![
-            result,
+            without_offsets(result),
             [
                 Token::new(TokenType::Print, String::from("print"), 2),
                 Token::new(
@@ -559,7 +886,7 @@ mod tests {
         let result: Vec<Token> = scanner.map(|i| i.unwrap()).collect();
 
         assert_eq!(
-            result,
+            without_offsets(result),
             [
                 Token::new(
                     TokenType::Identifier(String::from("a")),
@@ -587,7 +914,7 @@ mod tests {
         let result = scanner.scan_tokens().unwrap();
 
         assert_eq!(
-            result,
+            without_offsets(result),
             [
                 Token::new(TokenType::Fun, String::from("fun"), 1),
                 identifier!("function_example", 1),
@@ -609,4 +936,172 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn unknown_byte_line_recovery() {
+        let source = "var a = 1 @ 2;\nprint a;";
+        let mut scanner = super::Scanner::new(Cursor::new(source));
+        scanner.set_line_recovery(true);
+        let mut result: Vec<ScannerResult<Token>> = scanner.collect();
+
+        let error = result.remove(4).unwrap_err();
+        assert!(matches!(error.error_type, ErrorType::UnknownByte(b'@')));
+        assert_eq!(error.line, 1);
+
+        let tokens: Vec<Token> = result.into_iter().map(|t| t.unwrap()).collect();
+        assert_eq!(
+            without_offsets(tokens),
+            [
+                Token::new(TokenType::Var, String::from("var"), 1),
+                identifier!("a", 1),
+                Token::new(TokenType::Equal, String::from("="), 1),
+                Token::new(
+                    TokenType::Number(ordered_float::OrderedFloat(1.0)),
+                    String::from("1"),
+                    1
+                ),
+                Token::new(TokenType::Print, String::from("print"), 2),
+                identifier!("a", 2),
+                semicolon_token!(2),
+            ]
+        )
+    }
+
+    fn scan_single(source: &str) -> ScannerResult<Token> {
+        let mut scanner = super::Scanner::new(Cursor::new(source));
+        scanner.next().unwrap()
+    }
+
+    #[test]
+    fn char_literal_escapes() {
+        let cases = [
+            ("'a'", 'a'),
+            ("'é'", 'é'),
+            (r"'\n'", '\n'),
+            (r"'\t'", '\t'),
+            (r"'\\'", '\\'),
+            (r"'\''", '\''),
+            (r"'\x41'", 'A'),
+            (r"'\u{41}'", 'A'),
+            (r"'\u{1F980}'", '🦀'),
+        ];
+
+        for (source, expected) in cases {
+            let token = scan_single(source).unwrap();
+            assert_eq!(token.token_type(), &TokenType::Char(expected), "{source}");
+            assert_eq!(token.lexeme(), source);
+        }
+    }
+
+    #[test]
+    fn invalid_char_literals() {
+        let cases = [
+            ("''", ErrorType::EmptyCharLiteral),
+            ("'ab'", ErrorType::OverlongCharLiteral),
+            ("'a", ErrorType::UnterminatedCharLiteral),
+            (r"'\q'", ErrorType::InvalidEscape(String::from(r"\q"))),
+            (
+                r"'\u{D800}'",
+                ErrorType::InvalidEscape(String::from(r"\u{D800}")),
+            ),
+        ];
+
+        for (source, expected) in cases {
+            let error = scan_single(source).unwrap_err();
+            assert_eq!(error.error_type, expected, "{source}");
+        }
+    }
+
+    #[test]
+    fn string_literal_escapes() {
+        let token = scan_single(r#""say \"hi\"\n\ttab \u{41}""#).unwrap();
+        assert_eq!(
+            token.token_type(),
+            &TokenType::String(String::from("say \"hi\"\n\ttab A"))
+        );
+    }
+
+    #[test]
+    fn escaped_line_breaks_continue_strings() {
+        let tokens = scan("print \"split \\\nacross lines\\\r\n\";\nprint 1;");
+
+        assert_eq!(
+            tokens[1].token_type(),
+            &TokenType::String(String::from("split across lines"))
+        );
+        assert_eq!(tokens[1].line(), 3);
+        assert_eq!(tokens[4].line(), 4);
+    }
+
+    fn scan(source: &str) -> Vec<Token> {
+        super::Scanner::new(Cursor::new(source))
+            .scan_tokens()
+            .unwrap()
+    }
+
+    /// Replaces `range` of `old` with `text`, and checks that rescanning only the edit yields the
+    /// same tokens as scanning the whole edited source.
+    fn assert_rescan(old: &str, range: std::ops::Range<usize>, text: &str) {
+        let mut source = String::from(old);
+        source.replace_range(range.clone(), text);
+        let edited = range.start..range.start + text.len();
+        let line_delta =
+            text.matches('\n').count() as isize - old[range].matches('\n').count() as isize;
+
+        let rescanned =
+            super::Scanner::rescan_range(&scan(old), &source, edited, line_delta).unwrap();
+        assert_eq!(rescanned, scan(&source));
+    }
+
+    #[test]
+    fn rescan_edit_inside_token() {
+        let old = "var foo = 1;\nprint foo;\n".repeat(6);
+        assert_rescan(&old, 5..6, "ooba");
+        assert_rescan(&old, 30..31, "42");
+    }
+
+    #[test]
+    fn rescan_edit_across_tokens() {
+        let old = "var a = \"one\ntwo\";\nprint a;\n".repeat(6);
+
+        /* Removing lines and merging tokens */
+        assert_rescan(&old, 2..45, "r b");
+        /* Adding lines inside a multi-line string */
+        assert_rescan(&old, 14..15, "\n\nthree\n");
+        /* Splitting a multi-line string in two */
+        assert_rescan(&old, 13..13, "\"; \"");
+        /* Inserting a multi-line string between two lines */
+        assert_rescan(&old, 28..28, "\"\n\"");
+    }
+
+    #[test]
+    fn tokens_record_their_byte_offset() {
+        let tokens = scan("var a = \"é\nb\";\n  print a;");
+        let offsets: Vec<usize> = tokens.iter().map(Token::offset).collect();
+        assert_eq!(offsets, [0, 4, 6, 8, 14, 18, 24, 25]);
+    }
+
+    #[test]
+    fn rescan_rejects_invalid_edits() {
+        let old = "var a = 1;\nprint a;\nprint a;\n";
+        let rescan = |source: &str, range, line_delta| {
+            super::Scanner::rescan_range(&scan(old), source, range, line_delta)
+                .unwrap_err()
+                .error_type
+        };
+
+        assert_eq!(rescan(old, 5..40, 0), ErrorType::InvalidEditRange(5..40));
+        /* Replacing "1" with "2" cannot add a line */
+        let source = old.replace('1', "2");
+        assert_eq!(rescan(&source, 8..9, 1), ErrorType::InvalidLineDelta(1));
+    }
+
+    #[test]
+    fn lines_start_from_the_given_line() {
+        let tokens = super::Scanner::new_with_line(Cursor::new("a\nb"), 10)
+            .scan_tokens()
+            .unwrap();
+        let lines: Vec<usize> = tokens.iter().map(Token::line).collect();
+        assert_eq!(lines, [10, 11]);
+    }
 }
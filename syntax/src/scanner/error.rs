@@ -1,24 +1,54 @@
+use std::ops::Range;
+
 #[derive(Debug)]
 pub struct ScannerError {
     pub error_type: ErrorType,
     pub line: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum ErrorType {
     NotUtf8,
     UnknownByte(u8),
     UnterminatedStringLiteral,
+    UnterminatedCharLiteral,
+    EmptyCharLiteral,
+    OverlongCharLiteral,
+    InvalidEscape(String),
+    /// An edit handed to [`Scanner::rescan_range`](super::Scanner::rescan_range) that does not
+    /// fit in the source.
+    InvalidEditRange(Range<usize>),
+    /// A line delta handed to [`Scanner::rescan_range`](super::Scanner::rescan_range) that does
+    /// not match the edit.
+    InvalidLineDelta(isize),
 }
 
-impl std::fmt::Display for ScannerError {
+impl std::fmt::Display for ErrorType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let message = match self.error_type {
+        let message = match self {
             ErrorType::NotUtf8 => String::from("String is not a valid UTF-8 sequence"),
             ErrorType::UnknownByte(a) => format!("Byte {a} is unknown"),
             ErrorType::UnterminatedStringLiteral => String::from("Unterminated string literal"),
+            ErrorType::UnterminatedCharLiteral => String::from("Unterminated character literal"),
+            ErrorType::EmptyCharLiteral => String::from("Empty character literal"),
+            ErrorType::OverlongCharLiteral => {
+                String::from("Character literal must contain a single character")
+            }
+            ErrorType::InvalidEscape(sequence) => format!("Invalid escape sequence {sequence}"),
+            ErrorType::InvalidEditRange(range) => {
+                format!("Edit range {range:?} is not within the source")
+            }
+            ErrorType::InvalidLineDelta(delta) => {
+                format!("The edit cannot have changed the number of lines by {delta}")
+            }
         };
 
-        write!(f, "[line {}]: {message}", self.line)
+        f.write_str(&message)
+    }
+}
+
+impl std::fmt::Display for ScannerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[line {}]: {}", self.line, self.error_type)
     }
 }
@@ -1,44 +1,81 @@
-use crate::expression::{self, Expression};
+use crate::expression::Expression;
 use crate::token::Token;
+use std::fmt::{Display, Formatter, Write};
 
 pub type Block = Vec<Statement>;
 
-#[derive(Debug, Clone)]
+/// A `static var` in a class body. It belongs to the class, so every instance shares it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StaticField {
+    pub name: String,
+    pub initializer: Option<Expression>,
+    pub line: usize,
+}
+
+/// An arm of a `match` statement. A `pattern` of `None` is the `_` that matches any value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MatchArm {
+    pub pattern: Option<Expression>,
+    pub body: Statement,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Function {
     pub name: String,
     pub parameters: Vec<Token>,
     pub body: Block,
+    /// The line of the function name.
+    pub line: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Statement {
     Expression(Expression),
-    Print(Expression),
+    /// `print a, b, c;` prints every value separated by a space.
+    Print(Vec<Expression>),
     VariableDeclaration {
         name: String,
         initializer: Option<Expression>,
+        line: usize,
     },
     FunctionDeclaration(Function),
-    Block(Block),
+    Block {
+        statements: Block,
+        line: usize,
+    },
     If {
         condition: Expression,
         then_branch: Box<Statement>,
         else_branch: Option<Box<Statement>>,
+        line: usize,
     },
+    /// `else_branch` runs once the loop ends, unless it ended with a `break`.
     While {
         condition: Expression,
         body: Box<Statement>,
+        else_branch: Option<Box<Statement>>,
+        line: usize,
     },
     For {
         initializer: Option<Box<Statement>>,
         condition: Option<Expression>,
         increment: Option<Expression>,
         body: Box<Statement>,
+        else_branch: Option<Box<Statement>>,
+        line: usize,
     },
     ClassDeclaration {
         name: String,
         methods: Vec<Function>,
+        static_fields: Vec<StaticField>,
+        /// Methods declared with `static`, which are called on the class and have no `this`.
+        static_methods: Vec<Function>,
         super_class: Option<Expression>,
+        line: usize,
     },
     Return {
         keyword: Token,
@@ -50,4 +87,150 @@ pub enum Statement {
     Continue {
         keyword: Token,
     },
+    Throw {
+        keyword: Token,
+        expression: Expression,
+    },
+    /// Runs `body`, and then calls the `close` method of the value of `resource`, if it has
+    /// one, however the body was left.
+    With {
+        keyword: Token,
+        resource: Expression,
+        body: Block,
+    },
+    /// Runs the body of the first arm whose pattern equals the value, if any. Patterns are
+    /// literals, so they can be compared without running any code.
+    Match {
+        keyword: Token,
+        value: Expression,
+        arms: Vec<MatchArm>,
+    },
+    /// A `try` block whose thrown values are bound to `catch_name` while `catch_body` runs. The
+    /// `finally_body` runs afterwards, however the other two blocks were left.
+    Try {
+        body: Block,
+        catch_name: Token,
+        catch_body: Block,
+        finally_body: Option<Block>,
+        line: usize,
+    },
+}
+
+impl Statement {
+    /// Returns the source line the statement starts in. Statements that start with a keyword
+    /// report the line of the keyword, and the rest the line of their first expression.
+    pub fn line(&self) -> usize {
+        match self {
+            Statement::Expression(expr) => expr.line(),
+            Statement::Print(expressions) => expressions[0].line(),
+            Statement::FunctionDeclaration(function) => function.line,
+            Statement::VariableDeclaration { line, .. }
+            | Statement::Block { line, .. }
+            | Statement::If { line, .. }
+            | Statement::While { line, .. }
+            | Statement::For { line, .. }
+            | Statement::ClassDeclaration { line, .. }
+            | Statement::Try { line, .. } => *line,
+            Statement::Return { keyword, .. }
+            | Statement::Break { keyword }
+            | Statement::Continue { keyword }
+            | Statement::Throw { keyword, .. }
+            | Statement::With { keyword, .. }
+            | Statement::Match { keyword, .. } => keyword.line(),
+        }
+    }
+}
+
+/// Renders a single-line summary of the statement. Nested statements (block contents, loop
+/// bodies, branches) are not expanded.
+impl Display for Statement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Statement::Expression(expr) => write!(f, "{expr:?};"),
+            Statement::Print(expressions) => {
+                f.write_str("print ")?;
+                for (i, expr) in expressions.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{expr:?}")?;
+                }
+                f.write_char(';')
+            }
+            Statement::VariableDeclaration {
+                name,
+                initializer: Some(initializer),
+                ..
+            } => write!(f, "var {name} = {initializer:?};"),
+            Statement::VariableDeclaration {
+                name,
+                initializer: None,
+                ..
+            } => write!(f, "var {name};"),
+            Statement::FunctionDeclaration(function) => write!(f, "{function}"),
+            Statement::Block { statements, .. } => {
+                write!(f, "{{ {} statements }}", statements.len())
+            }
+            Statement::If { condition, .. } => write!(f, "if ({condition:?})"),
+            Statement::While { condition, .. } => write!(f, "while ({condition:?})"),
+            Statement::For {
+                initializer,
+                condition,
+                increment,
+                ..
+            } => {
+                f.write_str("for (")?;
+                match initializer {
+                    Some(initializer) => write!(f, "{initializer}")?,
+                    None => f.write_char(';')?,
+                }
+                if let Some(condition) = condition {
+                    write!(f, " {condition:?}")?;
+                }
+                f.write_char(';')?;
+                if let Some(increment) = increment {
+                    write!(f, " {increment:?}")?;
+                }
+                f.write_char(')')
+            }
+            Statement::ClassDeclaration {
+                name, super_class, ..
+            } => match super_class {
+                Some(super_class) => write!(f, "class {name} < {super_class:?}"),
+                None => write!(f, "class {name}"),
+            },
+            Statement::Return {
+                expression: Some(expression),
+                ..
+            } => write!(f, "return {expression:?};"),
+            Statement::Return {
+                expression: None, ..
+            } => f.write_str("return;"),
+            Statement::Break { .. } => f.write_str("break;"),
+            Statement::Continue { .. } => f.write_str("continue;"),
+            Statement::Throw { expression, .. } => write!(f, "throw {expression:?};"),
+            Statement::With { resource, .. } => write!(f, "with ({resource:?})"),
+            Statement::Match { value, arms, .. } => {
+                write!(f, "match ({value:?}) {{ {} arms }}", arms.len())
+            }
+            Statement::Try {
+                catch_name,
+                finally_body,
+                ..
+            } => {
+                write!(f, "try catch ({})", catch_name.lexeme())?;
+                if finally_body.is_some() {
+                    f.write_str(" finally")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let parameters: Vec<&str> = self.parameters.iter().map(Token::lexeme).collect();
+        write!(f, "fun {}({})", self.name, parameters.join(", "))
+    }
 }
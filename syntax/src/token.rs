@@ -1,12 +1,15 @@
 use std::fmt::{Display, Formatter};
 
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TokenType {
     /* Single character tokens */
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -14,20 +17,30 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Question,
+    Colon,
 
     /* 1-2 character tokens */
+    PlusPlus,
+    MinusMinus,
     Bang,
     BangEqual,
     Equal,
     EqualEqual,
+    Arrow,
     Greater,
     GreaterEqual,
     Less,
     LessEqual,
 
+    /* 3 character tokens */
+    AndEqual,
+    OrEqual,
+
     /* Literals */
     Identifier(String),
     String(String),
+    Char(char),
     Number(ordered_float::OrderedFloat<f64>),
 
     // Keywords
@@ -44,29 +57,149 @@ pub enum TokenType {
     Return,
     Break,
     Continue,
+    Throw,
+    Try,
+    Catch,
+    Finally,
+    Static,
     Super,
     This,
     True,
     Var,
     While,
+    With,
+    Match,
+    /// The `_` that matches any value in a `match` arm.
+    Underscore,
 
     #[deprecated]
     Eof,
 }
 
+impl TokenType {
+    /// Identifiers, strings, characters and numbers.
+    pub fn is_literal(&self) -> bool {
+        use TokenType::*;
+        matches!(self, Identifier(_) | String(_) | Char(_) | Number(_))
+    }
+
+    pub fn is_keyword(&self) -> bool {
+        use TokenType::*;
+        matches!(
+            self,
+            And | Class
+                | Else
+                | False
+                | Fun
+                | For
+                | If
+                | Nil
+                | Or
+                | Print
+                | Return
+                | Break
+                | Continue
+                | Throw
+                | Try
+                | Catch
+                | Finally
+                | Static
+                | Super
+                | This
+                | True
+                | Var
+                | While
+                | With
+                | Match
+                | Underscore
+        )
+    }
+
+    /// Arithmetic, comparison and assignment operators, along with the ternary's `?` and `:`
+    /// and the comma operator. The logical `and` and `or` are keywords instead.
+    pub fn is_operator(&self) -> bool {
+        use TokenType::*;
+        matches!(
+            self,
+            Minus
+                | Plus
+                | Slash
+                | Star
+                | Question
+                | Colon
+                | PlusPlus
+                | MinusMinus
+                | Bang
+                | BangEqual
+                | Equal
+                | EqualEqual
+                | Greater
+                | GreaterEqual
+                | Less
+                | LessEqual
+                | AndEqual
+                | OrEqual
+                | Comma
+        )
+    }
+
+    /// Keywords that begin a declaration or a statement other than an expression statement.
+    pub fn is_statement_start(&self) -> bool {
+        use TokenType::*;
+        matches!(
+            self,
+            Class
+                | Fun
+                | Var
+                | For
+                | If
+                | While
+                | Print
+                | Return
+                | Break
+                | Continue
+                | Throw
+                | Try
+                | With
+                | Match
+        )
+    }
+
+    #[allow(deprecated)]
+    pub fn is_eof(&self) -> bool {
+        matches!(self, TokenType::Eof)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Token {
     token_type: TokenType,
     lexeme: String,
     line: usize,
+    /// The byte offset of the first byte of the lexeme in the scanned source.
+    offset: usize,
 }
 
 impl Token {
+    /// Creates a token that starts at the beginning of the source, which suits tokens that were
+    /// not scanned from one.
     pub fn new(token_type: TokenType, lexeme: String, line: usize) -> Token {
+        Self::new_with_offset(token_type, lexeme, line, 0)
+    }
+
+    /// Creates a token whose lexeme starts `offset` bytes into the source.
+    pub fn new_with_offset(
+        token_type: TokenType,
+        lexeme: String,
+        line: usize,
+        offset: usize,
+    ) -> Token {
         Token {
             token_type,
             lexeme,
             line,
+            offset,
         }
     }
 
@@ -74,6 +207,12 @@ impl Token {
         &self.lexeme
     }
 
+    /// Returns the lexeme with its control characters (such as the line breaks of a multi-line
+    /// string) escaped, which is suitable for error messages.
+    pub fn display_lexeme(&self) -> String {
+        crate::escape::escape_control(&self.lexeme)
+    }
+
     pub fn token_type(&self) -> &TokenType {
         &self.token_type
     }
@@ -81,11 +220,107 @@ impl Token {
     pub fn line(&self) -> usize {
         self.line
     }
+
+    /// Returns the byte offset the lexeme starts at in the source.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.token_type.is_eof()
+    }
 }
 
 impl Display for Token {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         // TODO: Implement literal reading
-        write!(f, "{:?} {} ", self.token_type, self.lexeme)
+        write!(f, "{:?} {} ", self.token_type, self.display_lexeme())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Token, TokenType};
+
+    #[test]
+    fn display_lexeme_escapes_control_characters() {
+        let token = Token::new(
+            TokenType::String(String::from("a\nb\tc")),
+            String::from("\"a\nb\tc\u{7}\""),
+            2,
+        );
+
+        assert_eq!(token.display_lexeme(), r#""a\nb\tc\u{7}""#);
+        assert!(!token.to_string().contains('\n'));
+    }
+
+    #[test]
+    fn display_lexeme_keeps_backslashes() {
+        let token = Token::new(
+            TokenType::String(String::from("a\nb")),
+            String::from(r#""a\nb""#),
+            1,
+        );
+
+        assert_eq!(token.display_lexeme(), r#""a\nb""#);
+    }
+
+    #[test]
+    fn token_type_classification() {
+        use TokenType::*;
+
+        let literals = [
+            Identifier(std::string::String::from("x")),
+            String(std::string::String::from("s")),
+            Char('c'),
+            Number(1.0.into()),
+        ];
+        let keywords = [
+            And, Class, Nil, Return, Static, Try, While, With, Match, Underscore,
+        ];
+        let operators = [
+            Minus, Star, Question, BangEqual, Equal, LessEqual, OrEqual, Comma,
+        ];
+        let punctuation = [LeftParen, RightBrace, Dot, Semicolon, Arrow];
+
+        assert!(literals.iter().all(TokenType::is_literal));
+        assert!(keywords.iter().all(TokenType::is_keyword));
+        assert!(operators.iter().all(TokenType::is_operator));
+
+        for token_type in literals.iter().chain(&punctuation) {
+            assert!(!token_type.is_keyword() && !token_type.is_operator());
+        }
+        for token_type in keywords.iter().chain(&operators).chain(&punctuation) {
+            assert!(!token_type.is_literal());
+        }
+        assert!(!keywords.iter().any(TokenType::is_operator));
+    }
+
+    #[test]
+    fn statement_starts() {
+        use TokenType::*;
+
+        for token_type in [
+            Class, Fun, Var, For, If, While, Print, Return, Break, Continue,
+        ] {
+            assert!(token_type.is_statement_start());
+        }
+        for token_type in [
+            Else,
+            Catch,
+            This,
+            LeftBrace,
+            Semicolon,
+            Identifier(Default::default()),
+        ] {
+            assert!(!token_type.is_statement_start());
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn eof() {
+        assert!(Token::new(TokenType::Eof, Default::default(), 1).is_eof());
+        assert!(!Token::new(TokenType::Semicolon, String::from(";"), 1).is_eof());
     }
 }